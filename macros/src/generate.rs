@@ -22,6 +22,8 @@ pub(crate) fn generate(ast: extract::TaskComponents) -> pm2::TokenStream {
         handler_name,
     } = ast;
 
+    let is_sink = oport_name.is_empty();
+
     let mut poll_outer = Vec::new();
     let mut poll_inner = Vec::new();
 
@@ -30,8 +32,8 @@ pub(crate) fn generate(ast: extract::TaskComponents) -> pm2::TokenStream {
         let handler = quote!({
             match event {
                 DataEvent::Data(time, data) => self.handle_data(#port(data), time),
-                DataEvent::Watermark(time) => todo!(),
-                DataEvent::End => todo!(),
+                DataEvent::Watermark(_, time) => todo!(),
+                DataEvent::End(_) => todo!(),
             }
         });
         poll_outer.push(poll_port(&handler, quote!(#skip), quote!(#skip), port));
@@ -59,6 +61,8 @@ pub(crate) fn generate(ast: extract::TaskComponents) -> pm2::TokenStream {
                 pub event_time: DateTime,
                 #(pub #iport_name: ProvidedPort<DataPort<#iport_type>>,)*
                 #(pub #oport_name: RequiredPort<DataPort<#oport_type>>,)*
+                pub ctrl_iport: ProvidedPort<CtrlPort>,
+                pub ctrl_oport: RequiredPort<CtrlPort>,
                 #(pub #state_name: #state_type,)*
             }
 
@@ -76,6 +80,8 @@ pub(crate) fn generate(ast: extract::TaskComponents) -> pm2::TokenStream {
                     event_time: DateTime::unix_epoch(),
                     #(#iport_name: ProvidedPort::uninitialised(),)*
                     #(#oport_name: RequiredPort::uninitialised(),)*
+                    ctrl_iport: ProvidedPort::uninitialised(),
+                    ctrl_oport: RequiredPort::uninitialised(),
                     #(#state_name,)*
                 }
             }
@@ -118,9 +124,22 @@ pub(crate) fn generate(ast: extract::TaskComponents) -> pm2::TokenStream {
                         // TODO: We currently assume all tasks at least take one stream as input
                         let (stream, ..) = (#(&#iport_name,)*);
                         let task = stream.client.system().create(|| self);
+                        let node = stream.client.on_definition(|c| {
+                            let node = c.graph.add_node(
+                                stringify!(#task_name),
+                                std::any::type_name::<(#(#iport_type,)*)>(),
+                                std::any::type_name::<(#(#oport_type,)*)>(),
+                                false,
+                                #is_sink,
+                            );
+                            #(if let Some(from) = #iport_name.node {
+                                c.graph.add_edge(from, node);
+                            })*
+                            node
+                        });
                         // Step 2. Connect the input streams to each of the task's input ports
                         task.on_definition(|producer| {
-                            #((#iport_name.connector)(&mut producer.#iport_name);)*
+                            #((#iport_name.connector)(producer.ctx.id(), &mut producer.#iport_name, &mut producer.ctrl_oport);)*
                         });
                         // Step 3. Setup so that the task will be initialised eventually
                         {
@@ -135,15 +154,17 @@ pub(crate) fn generate(ast: extract::TaskComponents) -> pm2::TokenStream {
                         (#({
                             let producer = task.clone();
                             let connector: Arc<ConnectFn<_>>
-                                = Arc::new(move |iport| {
+                                = Arc::new(move |_, iport, oport| {
                                 producer.on_definition(|producer| {
                                     iport.connect(producer.#oport_name.share());
                                     producer.#oport_name.connect(iport.share());
+                                    oport.connect(producer.ctrl_iport.share());
+                                    producer.ctrl_iport.connect(oport.share());
                                 });
                             });
                             let client = stream.client.clone();
                             let start_fns = stream.start_fns.clone();
-                            Stream { client, connector, start_fns }
+                            Stream { client, connector, start_fns, producer: None, node: Some(node), chain: None, sides: Default::default() }
                         },)*)
                     }
                 }
@@ -154,6 +175,8 @@ pub(crate) fn generate(ast: extract::TaskComponents) -> pm2::TokenStream {
                     self.ctx.initialise(self_component.clone());
                     #(self.#iport_name.set_parent(self_component.clone());)*
                     #(self.#oport_name.set_parent(self_component.clone());)*
+                    self.ctrl_iport.set_parent(self_component.clone());
+                    self.ctrl_oport.set_parent(self_component.clone());
                 }
 
                 fn execute(&mut self, max_events: usize, skip: usize) -> ExecuteResult {
@@ -0,0 +1,105 @@
+#![allow(clippy::type_complexity)]
+
+use kompact::prelude::*;
+
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::data::*;
+use crate::loops::*;
+use crate::port::*;
+use crate::task::*;
+
+/// A future which resolves to the output of an asynchronous task for one input event.
+pub type TaskFuture<O> = Pin<Box<dyn Future<Output = Option<O>> + Send>>;
+/// Logic of an asynchronous task, which returns a future for each input event.
+pub type AsyncLogic<S, I, O, R> = Box<dyn FnMut(&mut Task<S, I, O, R>, I) -> TaskFuture<O> + Send>;
+
+impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
+    /// Create a task whose logic returns a future for each input event. The task emits the
+    /// output of each future once it resolves, if any. The task does not handle any other events
+    /// while it awaits a future, which means events are processed one at a time and in order.
+    ///
+    /// Asynchronous tasks are never fused with their neighbours, since a fused task handles its
+    /// input outside of its own component, where it cannot block on a future.
+    pub(crate) fn new_async<F>(
+        name: &'static str,
+        state: S,
+        mut logic: impl FnMut(&mut Self, I) -> F + Send + 'static,
+    ) -> Self
+    where
+        F: Future<Output = Option<O>> + Send + 'static,
+    {
+        Self {
+            async_logic: Some(Box::new(move |task, event| Box::pin(logic(task, event)))),
+            chaining: false,
+            ..Self::new(name, state, |_, _| {})
+        }
+    }
+
+    /// Run a future while the task continues to handle other events, and call `then` with its
+    /// output once it completes. The task does not end its output stream before all spawned
+    /// futures have completed.
+    pub(crate) fn spawn<F>(
+        &mut self,
+        future: F,
+        then: impl FnOnce(&mut Self, F::Output) + Send + 'static,
+    ) where
+        F: Future + Send + 'static,
+        F::Output: Send,
+    {
+        self.spawned += 1;
+        self.spawn_local(move |mut task| async move {
+            let output = future.await;
+            task.spawned -= 1;
+            then(&mut *task, output);
+            let buffer = &task.buffer;
+            if task.spawned == 0 && buffer.ended > 0 && buffer.ended >= buffer.producers {
+                task.finish();
+            }
+            Handled::Ok
+        });
+    }
+
+    /// Handle input data with asynchronous logic, blocking the task until all of its futures
+    /// have resolved.
+    pub(crate) fn handle_async(&mut self, event: DataEvent<I>) -> Handled {
+        // Each item of a batch was charged one credit
+        let acks = match &event {
+            DataEvent::Batch(batch) => batch.len(),
+            _ => 1,
+        };
+        let (scopes, items) = match event {
+            DataEvent::Item(time, data) => (Vec::new(), vec![(time, data)]),
+            DataEvent::Scoped(scopes, time, data) => (scopes, vec![(time, data)]),
+            DataEvent::Batch(batch) => (Vec::new(), batch.into_iter().collect()),
+            DataEvent::Routed(..) | DataEvent::Watermark(..) | DataEvent::End(_) => unreachable!(),
+        };
+        // The logic is called synchronously, so it can access the task before it returns
+        let mut logic = self.async_logic.take().unwrap();
+        let mut futures = Vec::new();
+        self.scopes = scopes.clone();
+        for (time, data) in items {
+            if time >= self.time {
+                self.event_time = time;
+                futures.push((time, logic(self, data)));
+            }
+        }
+        self.event_time = self.time;
+        self.scopes.clear();
+        self.async_logic = Some(logic);
+        Handled::block_on(self, move |mut task| async move {
+            for (time, future) in futures {
+                if let Some(data) = future.await {
+                    task.scopes = scopes.clone();
+                    task.emit_at(time, data);
+                    task.scopes.clear();
+                }
+            }
+            retire(&scopes);
+            for _ in 0..acks {
+                task.ack();
+            }
+        })
+    }
+}
@@ -0,0 +1,91 @@
+use arrayvec::ArrayVec;
+use kompact::prelude::*;
+
+use crate::data::*;
+use crate::port::*;
+use crate::route::*;
+use crate::task::*;
+
+use std::time::Duration;
+
+/// Maximum number of items in a batch.
+pub const BATCH_CAPACITY: usize = 64;
+
+/// A fixed-capacity batch of timestamped items.
+pub type Batch<T> = ArrayVec<(DateTime, T), BATCH_CAPACITY>;
+
+/// Accumulates emitted items into batches before they are sent.
+pub struct Batcher<O: DataReqs> {
+    pub items: Batch<O>,
+    /// Maximum time an item may wait in a batch.
+    pub timeout: Duration,
+    pub scheduled: Option<ScheduledTimer>,
+    /// Routes which the items of the batch are sent to, as a bitmask.
+    pub routing: Option<u64>,
+}
+
+impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
+    /// Send emitted items in batches. A batch is flushed when it is full, when a watermark or
+    /// the end of the stream is emitted, or when its oldest item has waited for `timeout`.
+    pub(crate) fn set_batching(self, timeout: Duration) -> Self {
+        Self {
+            batcher: Some(Batcher {
+                items: ArrayVec::new(),
+                timeout,
+                scheduled: None,
+                routing: None,
+            }),
+            ..self
+        }
+    }
+
+    /// Add an item to the current batch. The batch is flushed first if its items are sent to
+    /// other routes.
+    pub(crate) fn batch(&mut self, time: DateTime, data: O) {
+        if self.batcher.as_ref().unwrap().routing != self.routing {
+            self.flush_batch();
+        }
+        let batcher = self.batcher.as_mut().unwrap();
+        batcher.routing = self.routing;
+        batcher.items.push((time, data));
+        if batcher.items.is_full() {
+            self.flush_batch();
+        } else if batcher.scheduled.is_none() {
+            let timeout = batcher.timeout;
+            self.batcher.as_mut().unwrap().scheduled =
+                Some(self.schedule_once(timeout, Self::batch_timeout));
+        }
+    }
+
+    /// Send the current batch if it is non-empty.
+    pub(crate) fn flush_batch(&mut self) {
+        if let Some(batcher) = self.batcher.as_mut() {
+            let scheduled = batcher.scheduled.take();
+            let items = std::mem::replace(&mut batcher.items, ArrayVec::new());
+            let routes = batcher.routing.take();
+            if let Some(scheduled) = scheduled {
+                self.cancel_timer(scheduled);
+            }
+            if !items.is_empty() {
+                self.enqueue(routed(routes, DataEvent::Batch(items)));
+            }
+        }
+    }
+
+    fn batch_timeout(&mut self, timeout: ScheduledTimer) -> Handled {
+        match self.batcher.as_ref().and_then(|b| b.scheduled.as_ref()) {
+            Some(scheduled) if *scheduled == timeout => {
+                self.batcher.as_mut().unwrap().scheduled = None;
+                self.guard(|task| {
+                    task.flush_batch();
+                    Handled::Ok
+                })
+            }
+            Some(_) => Handled::Ok,
+            None => {
+                warn!(self.log(), "Got unexpected timeout: {:?}", timeout);
+                Handled::Ok
+            }
+        }
+    }
+}
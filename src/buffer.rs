@@ -0,0 +1,202 @@
+use kompact::prelude::*;
+use uuid::Uuid;
+
+use crate::data::*;
+use crate::port::*;
+use crate::route::*;
+use crate::task::*;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+
+/// Number of events a task buffers by default.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// Credit-based flow control of a task.
+///
+/// Emitted events are buffered until every consumer of the output port has granted credit for
+/// them. A task grants credit to its own producers as it consumes events, but holds it back while
+/// its output buffer is full. This way backpressure propagates upstream until it reaches a source.
+pub struct Buffer<O: DataReqs> {
+    /// Maximum number of buffered output events, and initial credit granted to producers.
+    pub capacity: usize,
+    /// Events which have been emitted but not yet sent.
+    pub queue: VecDeque<DataEvent<O>>,
+    /// Number of consumers connected to the output port.
+    pub consumers: usize,
+    /// Number of producers connected to the input port.
+    pub producers: usize,
+    /// Number of producers which have ended their stream.
+    pub ended: usize,
+    /// Remaining credit of each consumer.
+    pub credits: HashMap<Uuid, usize>,
+    /// Number of consumed input events for which no credit has been granted yet.
+    pub consumed: usize,
+    /// Whether the task should die once the buffer is drained.
+    pub terminating: bool,
+    /// Whether sending has been paused by the consumers.
+    pub paused: bool,
+    /// Consumers which have paused the task. Sending is paused once all consumers have.
+    pub pausing: HashSet<Uuid>,
+    /// Number of input events which the task holds without having emitted their output.
+    /// These count towards the capacity of the buffer.
+    pub held: usize,
+}
+
+impl<O: DataReqs> Buffer<O> {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            queue: VecDeque::new(),
+            consumers: 0,
+            producers: 0,
+            ended: 0,
+            credits: HashMap::new(),
+            consumed: 0,
+            terminating: false,
+            paused: false,
+            pausing: HashSet::new(),
+            held: 0,
+        }
+    }
+}
+
+impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
+    /// Set the capacity of the task's buffer.
+    pub(crate) fn set_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "Buffer capacity must be positive");
+        self.buffer.capacity = capacity;
+        self
+    }
+
+    /// Returns true if the output buffers of the task, including its side outputs, are full.
+    pub(crate) fn is_congested(&self) -> bool {
+        self.buffer.queue.len() + self.buffer.held + self.side_backlog() >= self.buffer.capacity
+    }
+
+    /// Buffer an event and send as many events as the consumers have credit for.
+    pub(crate) fn send(&mut self, event: DataEvent<O>) {
+        match event {
+            DataEvent::Item(time, data) if self.batcher.is_some() => return self.batch(time, data),
+            DataEvent::Item(..)
+            | DataEvent::Scoped(..)
+            | DataEvent::Batch(..)
+            | DataEvent::Routed(..) => {}
+            DataEvent::Watermark(..) | DataEvent::End(_) => self.flush_batch(),
+        }
+        let event = match event {
+            DataEvent::Watermark(..) | DataEvent::End(_) => event,
+            event => routed(self.routing, event),
+        };
+        self.enqueue(event);
+    }
+
+    /// Buffer an event without batching it.
+    pub(crate) fn enqueue(&mut self, event: DataEvent<O>) {
+        // Nothing may be sent after the end of the stream
+        if self.buffer.terminating {
+            warn!(self.log(), "Dropped event sent after the end of the stream");
+            return;
+        }
+        if let DataEvent::End(_) = event {
+            self.buffer.terminating = true;
+        }
+        self.buffer.queue.push_back(event);
+        self.flush();
+    }
+
+    /// Send buffered events downstream while all consumers have credit.
+    pub(crate) fn flush(&mut self) {
+        let congested = self.is_congested();
+        while !self.buffer.paused {
+            // Routed events only need credit from the consumers of their routes
+            let (routes, event) = match self.buffer.queue.pop_front() {
+                Some(DataEvent::Routed(routes, event)) => (Some(routes), *event),
+                Some(event) => (None, event),
+                None => break,
+            };
+            let available = match self.available(routes) {
+                Some(available) if available > 0 => available,
+                _ => {
+                    self.buffer.queue.push_front(routed(routes, event));
+                    break;
+                }
+            };
+            let event = match event {
+                // Send as many items of a batch as the consumers have credit for
+                DataEvent::Batch(mut batch) if batch.len() > available => {
+                    let rest = batch.drain(available..).collect();
+                    self.buffer.queue.push_front(routed(routes, DataEvent::Batch(rest)));
+                    DataEvent::Batch(batch)
+                }
+                event => event,
+            };
+            // Consumers grant one credit per item
+            let cost = match &event {
+                DataEvent::Batch(batch) => batch.len(),
+                _ => 1,
+            };
+            match routes {
+                Some(routes) => {
+                    let consumers: Vec<Uuid> = self.routed(routes).copied().collect();
+                    for id in consumers {
+                        *self.buffer.credits.get_mut(&id).unwrap() -= cost;
+                    }
+                }
+                None => {
+                    for credit in self.buffer.credits.values_mut() {
+                        *credit -= cost;
+                    }
+                }
+            }
+            self.deliver(routes, event);
+        }
+        if self.buffer.queue.is_empty() && self.buffer.terminating && self.side_backlog() == 0 {
+            self.ctx.suicide();
+        } else if congested && !self.is_congested() {
+            // Release the credit and timer which were held back by backpressure
+            self.grant();
+            self.resume_timer();
+        }
+    }
+
+    /// Record that an input event has been consumed.
+    pub(crate) fn ack(&mut self) {
+        self.buffer.consumed += 1;
+        if !self.is_congested() && self.buffer.consumed >= (self.buffer.capacity / 2).max(1) {
+            self.grant();
+        }
+    }
+
+    /// Grant credit for all consumed events to the producers.
+    pub(crate) fn grant(&mut self) {
+        if self.buffer.consumed > 0 {
+            let id = self.ctx.id();
+            // Tasks of side outputs receive their input through the side port
+            self.side_iport.trigger(DataReply::Pull(id, self.buffer.consumed));
+            self.data_iport.trigger(DataReply::Pull(id, self.buffer.consumed));
+            self.buffer.consumed = 0;
+        }
+    }
+
+    /// Add credit granted by a consumer.
+    pub(crate) fn credit(&mut self, id: Uuid, amount: usize) {
+        *self.buffer.credits.entry(id).or_insert(0) += amount;
+        self.flush();
+    }
+
+    /// Returns the lowest credit among the consumers of the given routes, or among all consumers
+    /// if the event is not routed, or `None` if any of them has not granted credit yet.
+    fn available(&self, routes: Option<u64>) -> Option<usize> {
+        match routes {
+            Some(routes) => self
+                .routed(routes)
+                .try_fold(usize::MAX, |min, id| Some(min.min(*self.buffer.credits.get(id)?))),
+            None if self.buffer.credits.len() >= self.buffer.consumers => {
+                Some(self.buffer.credits.values().copied().min().unwrap_or(usize::MAX))
+            }
+            None => None,
+        }
+    }
+}
@@ -0,0 +1,91 @@
+#![allow(clippy::type_complexity)]
+
+use kompact::prelude::*;
+
+use std::sync::Arc;
+
+use crate::client::*;
+use crate::data::*;
+use crate::graph::*;
+use crate::port::*;
+use crate::stream::*;
+use crate::task::*;
+
+/// Delivers the output events of a producer directly to a chained consumer.
+pub type ChainedFn<T> = Box<dyn FnMut(DataEvent<T>) + Send>;
+/// Chains the producer of a stream to a consumer.
+pub type ChainFn<T> = dyn Fn(ChainedFn<T>) + Send + Sync;
+
+/// A connection between two tasks which can be fused.
+pub(crate) struct Link {
+    pub(crate) from: NodeId,
+    pub(crate) to: NodeId,
+    pub(crate) fuse: Box<dyn FnOnce() + Send>,
+}
+
+impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
+    /// Prevent the task from being fused with its producer or consumer.
+    pub(crate) fn disable_chaining(self) -> Self {
+        Self {
+            chaining: false,
+            ..self
+        }
+    }
+
+    /// Handle an event which was delivered directly by a chained producer.
+    pub(crate) fn handle_chained(&mut self, event: DataEvent<I>) {
+        if let Handled::DieNow = self.guard(|task| task.handle_data(event)) {
+            self.ctx.suicide();
+        }
+    }
+}
+
+/// Returns a function which chains the output of a task to a consumer.
+pub(crate) fn create_chain<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs>(
+    producer: Arc<Component<Task<S, I, O, R>>>,
+) -> Arc<ChainFn<O>> {
+    Arc::new(move |chained| {
+        producer.on_definition(|producer| producer.chained = Some(chained));
+    })
+}
+
+/// Record that a task consumes a stream, so the two can be fused when the pipeline is
+/// finalized.
+pub(crate) fn link<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs>(
+    stream: &Stream<I>,
+    consumer: &Arc<Component<Task<S, I, O, R>>>,
+) {
+    let to = consumer.on_definition(|consumer| consumer.node);
+    if let (Some(from), Some(to), Some(chain)) = (stream.node, to, stream.chain.clone()) {
+        let consumer = consumer.clone();
+        let fuse = Box::new(move || {
+            let target = consumer.clone();
+            chain(Box::new(move |event| {
+                target.on_definition(|consumer| consumer.handle_chained(event))
+            }));
+        });
+        stream
+            .client
+            .on_definition(|c| c.links.push(Link { from, to, fuse }));
+    }
+}
+
+/// Fuse consecutive tasks of a pipeline where the producer has no other consumers and the
+/// consumer has no other producers. Events pass from a fused producer to its consumer through
+/// direct calls rather than through ports, so a chain of tasks processes an event back-to-back
+/// within the thread of its first task. Fused tasks are recorded in the graph of the pipeline.
+pub(crate) fn fuse(client: &Arc<Component<Client>>) {
+    let (graph, links) = client.on_definition(|c| (c.graph.clone(), std::mem::take(&mut c.links)));
+    for link in links {
+        let fanout = graph.edges.iter().filter(|(from, _)| *from == link.from);
+        let fanin = graph.edges.iter().filter(|(_, to)| *to == link.to);
+        if fanout.count() == 1
+            && fanin.count() == 1
+            && graph.nodes[link.from].chaining
+            && graph.nodes[link.to].chaining
+        {
+            (link.fuse)();
+            client.on_definition(|c| c.graph.fused.push((link.from, link.to)));
+        }
+    }
+}
@@ -2,21 +2,36 @@ use kompact::prelude::*;
 
 use std::sync::Arc;
 
+use crate::chain::*;
 use crate::control::*;
 use crate::data::*;
+use crate::graph::*;
 use crate::pipeline::*;
 use crate::port::*;
+use crate::status::*;
 use crate::task::*;
 
 #[derive(ComponentDefinition, Actor)]
 pub struct Client {
     pub ctx: ComponentContext<Self>,
+    /// Sources of the pipeline.
+    pub sources: Vec<ActorRef<TaskMessage<()>>>,
+    /// Status of the tasks of the pipeline.
+    pub monitor: Arc<Monitor>,
+    /// Logical graph of the pipeline.
+    pub graph: Graph,
+    /// Connections between tasks which may be fused.
+    pub(crate) links: Vec<Link>,
 }
 
 impl Client {
     pub(crate) fn new() -> Self {
         Self {
             ctx: ComponentContext::uninitialised(),
+            sources: Vec::new(),
+            monitor: Arc::new(Monitor::default()),
+            graph: Graph::default(),
+            links: Vec::new(),
         }
     }
 }
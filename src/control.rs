@@ -1,6 +1,146 @@
 use kompact::prelude::*;
 
-#[derive(Debug, Clone)]
-pub(crate) enum TaskMessage {
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::data::*;
+use crate::port::*;
+use crate::task::*;
+
+/// A message which may be sent to the mailbox of a task.
+#[derive(Debug)]
+pub enum TaskMessage<R: DataReqs> {
+    /// Gracefully stop the task.
     Die,
+    Promise(Ask<(), R>),
+    Reconfigure(Command),
+    /// Immediately stop the task.
+    Cancel,
+    /// All events inside a loop with a pointstamp less than or equal to this have been processed.
+    Notify(Vec<i32>),
+}
+
+impl<R: DataReqs> From<Command> for TaskMessage<R> {
+    fn from(config: Command) -> Self {
+        TaskMessage::Reconfigure(config)
+    }
+}
+
+/// A user-defined command which can be sent upstream through a `CtrlPort`.
+#[derive(Clone)]
+pub struct Command(Arc<dyn Any + Send + Sync>);
+
+impl Command {
+    pub fn new<T: Any + Send + Sync>(command: T) -> Self {
+        Self(Arc::new(command))
+    }
+
+    /// Returns the command if it is of type `T`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.0.downcast_ref()
+    }
+}
+
+impl fmt::Debug for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Command(..)")
+    }
+}
+
+/// A handle for reconfiguring a running task.
+#[derive(Clone)]
+pub struct Reconfigurer(pub(crate) Recipient<Command>);
+
+impl Reconfigurer {
+    /// Send a configuration update to the task.
+    pub fn reconfigure<C: Any + Send + Sync>(&self, config: C) {
+        self.0.tell(Command::new(config));
+    }
+}
+
+impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
+    /// Set a handler for user-defined commands sent by consumers.
+    /// Commands are forwarded upstream if no handler is set.
+    pub(crate) fn on_command(
+        self,
+        handler: impl FnMut(&mut Self, Command) + Send + 'static,
+    ) -> Self {
+        Self {
+            on_command: Some(Box::new(handler)),
+            ..self
+        }
+    }
+
+    /// Set a hook which updates the state when a configuration of type `C` is received, either
+    /// through a `Reconfigurer` or from a consumer.
+    pub(crate) fn on_reconfigure<C: Any + Clone>(
+        self,
+        mut hook: impl FnMut(&mut S, C) + Send + 'static,
+    ) -> Self {
+        Self {
+            on_reconfigure: Some(Box::new(move |state, config| {
+                match config.downcast_ref::<C>() {
+                    Some(config) => {
+                        hook(state, config.clone());
+                        true
+                    }
+                    None => false,
+                }
+            })),
+            ..self
+        }
+    }
+
+    /// Apply a configuration update. Returns false if the task does not accept it.
+    pub(crate) fn reconfigure(&mut self, config: &Command) -> bool {
+        match self.on_reconfigure.as_mut() {
+            Some(hook) => hook(&mut self.state, config),
+            None => false,
+        }
+    }
+
+    /// Stop producing events, fire the remaining event-time timers, and die once all buffered
+    /// events have been sent.
+    pub(crate) fn stop(&mut self) {
+        if let Some(scheduled) = self.ptimer.as_mut().and_then(|p| p.scheduled.take()) {
+            self.cancel_timer(scheduled);
+        }
+        self.finish();
+    }
+
+    /// End the output stream after firing the remaining event-time timers.
+    pub(crate) fn finish(&mut self) {
+        if !self.buffer.terminating {
+            self.drain_timers();
+            if let Some(mut callback) = self.on_end.take() {
+                callback(self);
+            }
+            self.send(DataEvent::End(self.ctx.id()));
+            self.end_side_outputs();
+        }
+    }
+
+    /// Send a control-event to the producers of this task.
+    pub(crate) fn send_upstream(&mut self, event: CtrlEvent) {
+        self.ctrl_oport.trigger(event);
+    }
+
+    /// Stop producing output until resumed.
+    pub(crate) fn pause(&mut self) {
+        self.buffer.paused = true;
+        if let Some(scheduled) = self.ptimer.as_mut().and_then(|p| p.scheduled.take()) {
+            self.cancel_timer(scheduled);
+        }
+    }
+
+    /// Resume producing output.
+    pub(crate) fn resume(&mut self) {
+        self.buffer.paused = false;
+        self.flush();
+        if !self.is_congested() {
+            self.grant();
+            self.resume_timer();
+        }
+    }
 }
@@ -0,0 +1,216 @@
+#![allow(clippy::type_complexity)]
+
+use kompact::prelude::*;
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use crate::data::*;
+use crate::loops::*;
+use crate::port::*;
+use crate::stream::*;
+use crate::task::*;
+
+/// Order in which an enrichment emits the results of its requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Completion {
+    /// Emit results in the order their input events arrived.
+    Ordered,
+    /// Emit results as soon as their requests complete.
+    Unordered,
+}
+
+/// An asynchronous request which is issued for an input event.
+pub type Request<I, O> = dyn Fn(I) -> Pin<Box<dyn Future<Output = O> + Send>> + Send + Sync;
+
+/// State of an enrichment task.
+///
+/// Every input event gets a sequence number. An event is outstanding until the result of its
+/// request has been emitted, and a watermark is held back until all events which arrived before
+/// it are no longer outstanding.
+#[derive(Clone)]
+pub struct Enrich<I: DataReqs, O: DataReqs> {
+    request: Arc<Request<I, O>>,
+    concurrency: usize,
+    completion: Completion,
+    /// Sequence number of the next input event.
+    next: u64,
+    /// Number of requests which are in flight.
+    in_flight: usize,
+    /// Events which wait for a request to complete before their own is issued.
+    queue: VecDeque<(u64, Input<I>)>,
+    outstanding: BTreeSet<u64>,
+    /// Results which wait for the results of earlier events, if ordered.
+    completed: BTreeMap<u64, (Input<()>, O)>,
+    /// Held back watermarks, together with the sequence number of the next event at the time
+    /// they arrived.
+    watermarks: VecDeque<(u64, DateTime)>,
+}
+
+/// An input event of an enrichment, together with the time and loops it arrived with.
+#[derive(Debug, Clone)]
+struct Input<I> {
+    time: DateTime,
+    scopes: Vec<Frame>,
+    event: I,
+}
+
+impl<I: DataReqs, O: DataReqs> fmt::Debug for Enrich<I, O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Enrich")
+            .field("concurrency", &self.concurrency)
+            .field("completion", &self.completion)
+            .field("in_flight", &self.in_flight)
+            .field("outstanding", &self.outstanding)
+            .field("watermarks", &self.watermarks)
+            .finish()
+    }
+}
+
+impl<I: DataReqs> Stream<I> {
+    /// Enrich each event of a stream with the result of an asynchronous request, such as a
+    /// lookup in a key-value service. At most `concurrency` requests are in flight at a time.
+    /// Watermarks are held back until the results of all earlier events have been emitted.
+    pub(crate) fn enrich<O: DataReqs, F>(
+        self,
+        concurrency: usize,
+        completion: Completion,
+        request: impl Fn(I) -> F + Send + Sync + 'static,
+    ) -> Stream<O>
+    where
+        F: Future<Output = O> + Send + 'static,
+    {
+        assert!(concurrency > 0, "Concurrency must be positive");
+        let state = Enrich {
+            request: Arc::new(move |event| Box::pin(request(event))),
+            concurrency,
+            completion,
+            next: 0,
+            in_flight: 0,
+            queue: VecDeque::new(),
+            outstanding: BTreeSet::new(),
+            completed: BTreeMap::new(),
+            watermarks: VecDeque::new(),
+        };
+        self.apply(
+            Task::new(
+                "Enrich",
+                state,
+                |task: &mut Task<Enrich<I, O>, I, O, Never>, event: I| {
+                    let seq = task.state.next;
+                    task.state.next += 1;
+                    task.state.outstanding.insert(seq);
+                    task.buffer.held = task.state.outstanding.len();
+                    // Keep the loops of the event from terminating until its result is emitted
+                    produce(&task.scopes, 1);
+                    let input = Input {
+                        time: task.event_time,
+                        scopes: task.scopes.clone(),
+                        event,
+                    };
+                    if task.state.in_flight < task.state.concurrency {
+                        issue(task, seq, input);
+                    } else {
+                        task.state.queue.push_back((seq, input));
+                    }
+                },
+            )
+            .on_watermark(|task, time| {
+                let next = task.state.next;
+                task.state.watermarks.push_back((next, time));
+                release(task);
+            })
+            .hold_watermarks()
+            .disable_chaining(),
+        )
+    }
+}
+
+/// Issue the request of an event.
+fn issue<I: DataReqs, O: DataReqs>(
+    task: &mut Task<Enrich<I, O>, I, O, Never>,
+    seq: u64,
+    input: Input<I>,
+) {
+    task.state.in_flight += 1;
+    let future = (task.state.request)(input.event);
+    let input = Input {
+        time: input.time,
+        scopes: input.scopes,
+        event: (),
+    };
+    task.spawn(future, move |task, output| {
+        complete(task, seq, input, output)
+    });
+}
+
+/// Emit the result of a request, together with any results and watermarks it was holding back,
+/// and issue the request of the next waiting event.
+fn complete<I: DataReqs, O: DataReqs>(
+    task: &mut Task<Enrich<I, O>, I, O, Never>,
+    seq: u64,
+    input: Input<()>,
+    output: O,
+) {
+    let congested = task.is_congested();
+    task.state.in_flight -= 1;
+    match task.state.completion {
+        Completion::Unordered => {
+            task.state.outstanding.remove(&seq);
+            emit_result(task, input, output);
+        }
+        Completion::Ordered => {
+            task.state.completed.insert(seq, (input, output));
+            while let Some(first) = task.state.outstanding.iter().next().copied() {
+                match task.state.completed.remove(&first) {
+                    Some((input, output)) => {
+                        task.state.outstanding.remove(&first);
+                        emit_result(task, input, output);
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+    release(task);
+    if let Some((seq, input)) = task.state.queue.pop_front() {
+        issue(task, seq, input);
+    }
+    task.buffer.held = task.state.outstanding.len();
+    // Release the credit which was held back while requests were outstanding
+    if congested && !task.is_congested() {
+        task.grant();
+    }
+}
+
+/// Emit the result of a request with the time and loops of its input event.
+fn emit_result<I: DataReqs, O: DataReqs>(
+    task: &mut Task<Enrich<I, O>, I, O, Never>,
+    input: Input<()>,
+    output: O,
+) {
+    task.scopes = input.scopes;
+    task.emit_at(input.time, output);
+    let scopes = std::mem::take(&mut task.scopes);
+    retire(&scopes);
+}
+
+/// Forward the watermarks which no outstanding event is holding back.
+fn release<I: DataReqs, O: DataReqs>(task: &mut Task<Enrich<I, O>, I, O, Never>) {
+    let low = match task.state.outstanding.iter().next() {
+        Some(seq) => *seq,
+        None => task.state.next,
+    };
+    while let Some((boundary, time)) = task.state.watermarks.front().copied() {
+        if boundary > low {
+            break;
+        }
+        task.state.watermarks.pop_front();
+        task.send_watermark(time);
+    }
+}
@@ -9,20 +9,42 @@ use std::sync::Arc;
 use crate::client::*;
 use crate::control::*;
 use crate::data::*;
+use crate::status::*;
 use crate::task::*;
 
 pub(crate) struct Executor {
     pub(crate) system: KompactSystem,
+    /// Monitors of all pipelines created by the executor.
+    pub(crate) pipelines: RefCell<Vec<Arc<Monitor>>>,
+    /// Whether to keep running after all pipelines have finished.
+    pub(crate) keep_alive: bool,
 }
 
 impl Executor {
     pub(crate) fn new() -> Self {
         Executor {
             system: KompactConfig::default().build().expect("system"),
+            pipelines: RefCell::new(Vec::new()),
+            keep_alive: false,
         }
     }
 
+    /// Keep running after all pipelines have finished, for example in long-running services
+    /// which create new pipelines on demand.
+    pub(crate) fn set_keep_alive(self, keep_alive: bool) -> Self {
+        Self { keep_alive, ..self }
+    }
+
+    /// Run until all pipelines, including those spawned by tasks, have finished.
+    /// Runs forever if the executor is kept alive.
     pub(crate) fn execute(self) {
-        self.system.await_termination()
+        if self.keep_alive {
+            self.system.await_termination()
+        } else {
+            for monitor in self.pipelines.borrow().iter() {
+                monitor.await_all();
+            }
+            self.system.shutdown().expect("shutdown")
+        }
     }
 }
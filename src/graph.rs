@@ -0,0 +1,220 @@
+use std::fmt;
+use std::fmt::Write;
+
+/// Identifies a node in a logical graph.
+pub type NodeId = usize;
+
+/// A task in a logical graph.
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub id: NodeId,
+    pub name: &'static str,
+    pub input: &'static str,
+    pub output: &'static str,
+    /// Whether the task may be fused with its neighbours.
+    pub chaining: bool,
+    /// Whether the task is a sink, which has no output stream.
+    pub sink: bool,
+}
+
+/// The logical graph of a pipeline, recorded while it is built.
+#[derive(Debug, Clone, Default)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<(NodeId, NodeId)>,
+    /// Heads of loops, which must receive feedback from inside their loop.
+    pub loops: Vec<NodeId>,
+    /// Edges whose tasks have been fused when the pipeline was finalized.
+    pub fused: Vec<(NodeId, NodeId)>,
+}
+
+/// An error in the logical graph of a pipeline.
+#[derive(Debug, Clone)]
+pub enum GraphError {
+    /// The output stream of a task is never consumed.
+    DanglingStream { node: NodeId, name: &'static str },
+    /// The feedback stream of a loop does not come from inside the loop.
+    UnconnectedFeedback { node: NodeId, name: &'static str },
+    /// The output of a task never reaches a sink.
+    NoPathToSink { node: NodeId, name: &'static str },
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::DanglingStream { node, name } => write!(
+                f,
+                "The output stream of task {} ({}) is never consumed",
+                name, node
+            ),
+            GraphError::UnconnectedFeedback { node, name } => write!(
+                f,
+                "Loop head {} ({}) is not fed back from inside its loop",
+                name, node
+            ),
+            GraphError::NoPathToSink { node, name } => write!(
+                f,
+                "The output of task {} ({}) never reaches a sink",
+                name, node
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+impl Graph {
+    pub fn add_node(
+        &mut self,
+        name: &'static str,
+        input: &'static str,
+        output: &'static str,
+        chaining: bool,
+        sink: bool,
+    ) -> NodeId {
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            id,
+            name,
+            input,
+            output,
+            chaining,
+            sink,
+        });
+        id
+    }
+
+    pub fn add_edge(&mut self, from: NodeId, to: NodeId) {
+        self.edges.push((from, to));
+    }
+
+    /// Check that every stream is consumed, that every loop is fed back from inside itself, and
+    /// that every task has a path to a sink.
+    pub fn validate(&self) -> Result<(), GraphError> {
+        for node in &self.nodes {
+            if !node.sink && !self.edges.iter().any(|(from, _)| *from == node.id) {
+                return Err(GraphError::DanglingStream {
+                    node: node.id,
+                    name: node.name,
+                });
+            }
+        }
+        for head in &self.loops {
+            let inside = self.reachable(*head);
+            if !self
+                .edges
+                .iter()
+                .any(|(from, to)| to == head && inside[*from])
+            {
+                return Err(GraphError::UnconnectedFeedback {
+                    node: *head,
+                    name: self.nodes[*head].name,
+                });
+            }
+        }
+        for node in &self.nodes {
+            let reachable = self.reachable(node.id);
+            if !node.sink && !self.nodes.iter().any(|n| n.sink && reachable[n.id]) {
+                return Err(GraphError::NoPathToSink {
+                    node: node.id,
+                    name: node.name,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns which nodes can be reached by following at least one edge from a node.
+    fn reachable(&self, start: NodeId) -> Vec<bool> {
+        let mut reachable = vec![false; self.nodes.len()];
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            for (_, to) in self.edges.iter().filter(|(from, _)| *from == node) {
+                if !reachable[*to] {
+                    reachable[*to] = true;
+                    stack.push(*to);
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Returns true if the tasks of an edge have been fused.
+    pub fn is_fused(&self, from: NodeId, to: NodeId) -> bool {
+        self.fused.contains(&(from, to))
+    }
+
+    /// Render the graph in the Graphviz DOT language. Edges between fused tasks are bold.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph pipeline {\n");
+        for node in &self.nodes {
+            writeln!(
+                dot,
+                "    {} [label=\"{}\\n{} -> {}\"];",
+                node.id,
+                escape(node.name),
+                escape(node.input),
+                escape(node.output)
+            )
+            .unwrap();
+        }
+        for (from, to) in &self.edges {
+            if self.is_fused(*from, *to) {
+                writeln!(dot, "    {} -> {} [style=bold];", from, to).unwrap();
+            } else {
+                writeln!(dot, "    {} -> {};", from, to).unwrap();
+            }
+        }
+        dot.push('}');
+        dot
+    }
+
+    /// Render the graph as JSON. Each edge records whether its tasks have been fused.
+    pub fn to_json(&self) -> String {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|node| {
+                format!(
+                    r#"{{"id":{},"name":"{}","input":"{}","output":"{}"}}"#,
+                    node.id,
+                    escape(node.name),
+                    escape(node.input),
+                    escape(node.output)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let edges = self
+            .edges
+            .iter()
+            .map(|(from, to)| {
+                format!(
+                    r#"{{"from":{},"to":{},"fused":{}}}"#,
+                    from,
+                    to,
+                    self.is_fused(*from, *to)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(r#"{{"nodes":[{}],"edges":[{}]}}"#, nodes, edges)
+    }
+}
+
+/// Escape a string for use inside double quotes.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,177 @@
+#![allow(clippy::type_complexity)]
+
+use kompact::prelude::*;
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Add;
+use std::sync::Arc;
+
+use crate::data::*;
+use crate::stream::*;
+use crate::task::*;
+
+/// State which is partitioned by key.
+#[derive(Debug, Clone)]
+pub struct KeyedState<K: Hash + Eq, V>(HashMap<K, V>);
+
+impl<K: Hash + Eq, V> Default for KeyedState<K, V> {
+    fn default() -> Self {
+        Self(HashMap::new())
+    }
+}
+
+impl<K: Hash + Eq, V> KeyedState<K, V> {
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.0.get(key)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.0.get_mut(key)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.0.insert(key, value)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.0.remove(key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.0.iter()
+    }
+}
+
+/// A stream whose events are partitioned by key.
+#[derive(Clone)]
+pub struct KeyedStream<K, T: DataReqs> {
+    pub stream: Stream<T>,
+    pub key: Arc<dyn Fn(&T) -> K + Send + Sync>,
+}
+
+impl<T: DataReqs> Stream<T> {
+    /// Partition the events of a stream by key.
+    pub fn key_by<K: DataReqs + Hash + Eq>(
+        self,
+        key: impl Fn(&T) -> K + Send + Sync + 'static,
+    ) -> KeyedStream<K, T> {
+        KeyedStream {
+            stream: self,
+            key: Arc::new(key),
+        }
+    }
+}
+
+impl<K: DataReqs + Hash + Eq, T: DataReqs> KeyedStream<K, T> {
+    /// Aggregate the events of each key into an accumulator, and emit the updated result of the
+    /// key for each event. The accumulator of a key is created from its first event by `init`,
+    /// and updated with each subsequent event by `update`.
+    pub fn aggregate<A: DataReqs, O: DataReqs>(
+        self,
+        name: &'static str,
+        mut init: impl FnMut(T) -> A + Send + 'static,
+        mut update: impl FnMut(&mut A, T) + Send + 'static,
+        result: impl Fn(&A) -> O + Send + 'static,
+    ) -> Stream<(K, O)> {
+        let key = self.key;
+        self.stream.apply(Task::new(
+            name,
+            KeyedState::default(),
+            move |task: &mut Task<KeyedState<K, A>, T, (K, O), Never>, event: T| {
+                let key = key(&event);
+                let output = match task.state.get_mut(&key) {
+                    Some(acc) => {
+                        update(acc, event);
+                        result(acc)
+                    }
+                    None => {
+                        let acc = init(event);
+                        let output = result(&acc);
+                        task.state.insert(key.clone(), acc);
+                        output
+                    }
+                };
+                task.emit((key, output));
+            },
+        ))
+    }
+
+    /// Combine the events of each key.
+    pub fn reduce(self, mut f: impl FnMut(&T, T) -> T + Send + 'static) -> Stream<(K, T)> {
+        self.aggregate(
+            "Reduce",
+            |event| event,
+            move |acc, event| *acc = f(acc, event),
+            T::clone,
+        )
+    }
+
+    /// Sum the events of each key.
+    pub fn sum(self) -> Stream<(K, T)>
+    where
+        T: Add<Output = T>,
+    {
+        self.aggregate(
+            "Sum",
+            |event| event,
+            |acc, event| *acc = acc.clone() + event,
+            T::clone,
+        )
+    }
+
+    /// Find the smallest event of each key.
+    pub fn min(self) -> Stream<(K, T)>
+    where
+        T: PartialOrd,
+    {
+        self.aggregate(
+            "Min",
+            |event| event,
+            |acc, event| {
+                if event < *acc {
+                    *acc = event;
+                }
+            },
+            T::clone,
+        )
+    }
+
+    /// Find the largest event of each key.
+    pub fn max(self) -> Stream<(K, T)>
+    where
+        T: PartialOrd,
+    {
+        self.aggregate(
+            "Max",
+            |event| event,
+            |acc, event| {
+                if event > *acc {
+                    *acc = event;
+                }
+            },
+            T::clone,
+        )
+    }
+
+    /// Count the events of each key.
+    pub fn count(self) -> Stream<(K, u64)> {
+        self.aggregate("Count", |_| 1, |acc, _| *acc += 1, |acc| *acc)
+    }
+
+    /// Average the events of each key.
+    pub fn avg(self) -> Stream<(K, f64)>
+    where
+        T: Into<f64>,
+    {
+        self.aggregate(
+            "Average",
+            |event| (event.into(), 1),
+            |(sum, count): &mut (f64, u64), event| {
+                *sum += event.into();
+                *count += 1;
+            },
+            |(sum, count)| sum / *count as f64,
+        )
+    }
+}
@@ -3,34 +3,62 @@
 #![feature(arbitrary_self_types)]
 #![allow(unused)]
 
+pub mod asynchronous;
+pub mod batch;
+pub mod buffer;
+pub mod chain;
 pub mod client;
 pub mod control;
 pub mod data;
+pub mod enrich;
 pub mod epochs;
 pub mod executor;
+pub mod graph;
+pub mod keyed;
 pub mod loops;
+pub mod operators;
 pub mod pipeline;
 pub mod port;
+pub mod route;
+pub mod side;
 pub mod sink;
+pub mod sketch;
 pub mod source;
+pub mod status;
 pub mod stream;
 pub mod task;
 pub mod timer;
+pub mod topk;
 pub mod transform;
+pub mod window;
 
 pub mod prelude {
+    pub use crate::asynchronous::*;
+    pub use crate::batch::*;
+    pub use crate::buffer::*;
+    pub use crate::chain::*;
     pub use crate::client::*;
     pub use crate::control::*;
     pub use crate::data::*;
+    pub use crate::enrich::*;
     pub use crate::epochs::*;
     pub use crate::executor::*;
+    pub use crate::graph::*;
+    pub use crate::keyed::*;
     pub use crate::loops::*;
+    pub use crate::operators::*;
     pub use crate::pipeline::*;
     pub use crate::port::*;
+    pub use crate::route::*;
+    pub use crate::side::*;
     pub use crate::sink::*;
+    pub use crate::sketch::*;
     pub use crate::source::*;
+    pub use crate::status::*;
     pub use crate::stream::*;
+    pub use crate::topk::*;
     pub use crate::transform::*;
+    pub use crate::window::*;
     // pub use crate::task;
     pub use kompact::prelude::*;
     pub use std::any::Any;
@@ -4,15 +4,22 @@ use kompact::component::AbstractComponent;
 use kompact::prelude::*;
 
 use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt;
 use std::marker::PhantomData;
 use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::sync::Mutex;
 
 use crate::client::*;
 use crate::control::*;
 use crate::data::*;
 use crate::pipeline::*;
 use crate::port::*;
+use crate::status::*;
 use crate::stream::*;
 use crate::task::*;
 
@@ -27,52 +34,441 @@ impl<I: DataReqs> Stream<I> {
     ///        (s1, s3)
     ///    })
     /// ```
+    ///
+    /// The loop terminates, and its output ends, once the input has ended and no events remain
+    /// inside the loop.
     pub(crate) fn iterate<O: DataReqs>(
         self,
         f: fn(Stream<I>) -> (Stream<I>, Stream<O>),
     ) -> Stream<O> {
-        let task = Task::new(
-            "LoopHead",
-            (),
-            |task: &mut Task<(), I, I, Never>, event: I| {
-                task.emit(event);
+        let progress = Arc::new(Progress::default());
+        let entry = self.apply(loop_entry(progress.clone()));
+        let (task_feedback, stream) = loop_head(entry, &progress);
+        let (feedback, output) = f(stream);
+        let tail = feedback.apply(loop_tail());
+        task_feedback.on_definition(|consumer| consumer.connect_input(&tail));
+        output.apply(loop_exit(1))
+    }
+
+    /// Run a bulk-synchronous iteration over each epoch of a stream.
+    ///
+    /// The watermarks of the input divide it into epochs, which are iterated independently, so
+    /// an epoch does not wait for the input to end. Each superstep runs `body` over all messages
+    /// of the previous round of an epoch, starting with the messages of the epoch itself. A
+    /// barrier separates the rounds: messages produced in a round are folded into an aggregate
+    /// with `aggregate`, and held back until the round is complete. Then `converged` is called
+    /// with the aggregate and the number of the round. If it returns true, the held back
+    /// messages are emitted on the output stream, otherwise they become the input of the next
+    /// round. The iteration of an epoch also ends if a round produces no messages.
+    /// ```ignore
+    /// stream.supersteps(
+    ///     0,
+    ///     |messages| Relax(..) (messages),
+    ///     |changed, _| *changed += 1,
+    ///     |changed, round| *changed == 0 || round >= 30,
+    /// )
+    /// ```
+    pub(crate) fn supersteps<A: DataReqs>(
+        self,
+        init: A,
+        body: fn(Stream<I>) -> Stream<I>,
+        aggregate: fn(&mut A, &I),
+        converged: fn(&A, i32) -> bool,
+    ) -> Stream<I> {
+        let rounds = Arc::new(Progress::default());
+        let entry = self.apply(superstep_entry(rounds.clone()));
+        let (head, stream) = loop_head(entry, &rounds);
+        let barrier = Task::new(
+            "Superstep",
+            Superstep {
+                init,
+                fold: aggregate,
+                converged,
+                rounds: HashMap::new(),
             },
-        );
-        let start_fns = self.start_fns.clone();
-        let client = self.client.clone();
-        let task = self.client.system().create(|| task);
-        task.on_definition(|consumer| (self.connector)(&mut consumer.data_iport));
-        let producer = task.clone();
-        let connector: Arc<ConnectFn<_>> = Arc::new(move |iport| {
-            producer.on_definition(|producer| {
-                iport.connect(producer.data_oport.share());
-                producer.data_oport.connect(iport.share());
-            });
+            |task: &mut Task<Superstep<A, I>, I, Either<I, I>, Never>, event: I| {
+                let round = pointstamp(&task.scopes);
+                let context = round[..round.len() - 1].to_vec();
+                let mut frames = task.scopes.clone();
+                frames.last_mut().unwrap().counter += 1;
+                // Hold the message until the round is complete
+                produce(&frames, 1);
+                let first = !task.state.rounds.contains_key(&context);
+                let init = task.state.init.clone();
+                let (aggregate, pending) = task
+                    .state
+                    .rounds
+                    .entry(context)
+                    .or_insert_with(|| (init, Vec::new()));
+                (task.state.fold)(aggregate, &event);
+                pending.push((frames, event));
+                if first {
+                    let progress = task.scopes.last().unwrap().progress.clone();
+                    progress.notify_at(round, task.actor_ref());
+                }
+            },
+        )
+        .on_notify(|task, round| {
+            let (round, context) = round.split_last().unwrap();
+            let (aggregate, pending) = task.state.rounds.remove(context).unwrap();
+            let done = (task.state.converged)(&aggregate, *round);
+            // Both sides of the split are consumers of the barrier, so each of them retires
+            // every message which is emitted here
+            for (frames, event) in pending {
+                task.scopes = frames.clone();
+                task.emit(if done {
+                    Either::R(event)
+                } else {
+                    Either::L(event)
+                });
+                task.scopes.clear();
+                retire(&frames);
+            }
         });
-        let task_feedback = task.clone();
-        start_fns
-            .borrow_mut()
-            .push(Box::new(move || client.system().start(&task)));
-        let client = self.client.clone();
-        let stream = Stream::new(client, connector, start_fns);
-        let (feedback, output) = f(stream);
-        task_feedback.on_definition(|consumer| (feedback.connector)(&mut consumer.data_iport));
-        output
+        let (feedback, output) = body(stream).split(barrier);
+        head.on_definition(|consumer| consumer.connect_input(&feedback));
+        // Remove both the round and the epoch of messages exiting the iteration
+        output.apply(loop_exit(2))
     }
 
-    fn structured_loop<R: DataReqs, O: DataReqs, const A: usize>(
+    /// Run a structured loop over a stream.
+    ///
+    /// Events entering the loop get a loop counter which starts at zero. The `body` receives
+    /// the entering stream and the feedback stream, and returns the stream which is fed back
+    /// and the stream which exits the loop. Fed back events have their counter incremented and
+    /// are only iterated again if `cond` holds for them and the counters of the `A` innermost
+    /// loops they are inside. Events exiting the loop have their counter removed.
+    ///
+    /// The loop terminates when its input has ended and no events remain inside it.
+    /// ```ignore
+    /// stream
+    ///     .structured_loop(
+    ///         |entry: Stream<i32>, feedback: Stream<i32>| {
+    ///             let s = Map(|x| x + 1) (entry.merge(feedback));
+    ///             (s.clone(), s)
+    ///         },
+    ///         |x, Scope([i])| i < 10,
+    ///     )
+    /// ```
+    pub(crate) fn structured_loop<R: DataReqs, O: DataReqs, const A: usize>(
         self,
         body: fn(Stream<I>, Stream<R>) -> (Stream<R>, Stream<O>),
         cond: fn(R, Scope<A>) -> bool,
     ) -> Stream<O> {
-        todo!()
-        // Iteration Head => Termination condition
-        // Iteration Tail
-        // Entry
-        // Exit
-        // Progress = [pn, pn-1, pn-2, .. p0] = [Current|Context]
-        // P ≥ P′ iff (Pctx = P′ctx) ∧ (PT ≥ P′T)
+        let progress = Arc::new(Progress::default());
+        let entry = self.apply(loop_entry(progress.clone()));
+        // Iteration head, which receives fed back events after the tail has incremented their
+        // counters
+        let head = Task::new(
+            "LoopHead",
+            cond,
+            |task: &mut Task<fn(R, Scope<A>) -> bool, R, R, Never>, event: R| {
+                let scope = Scope::new(&task.scopes);
+                if (task.state)(event.clone(), scope) {
+                    task.emit(event);
+                }
+            },
+        );
+        let client = entry.client.clone();
+        let start_fns = entry.start_fns.clone();
+        let head = create_task(&client, head);
+        register_loop(&client, &head);
+        progress.set_head(head.actor_ref());
+        let feedback = Stream::from_task(entry.client.clone(), start_fns.clone(), &head);
+        let head_tail = head.clone();
+        start_fns
+            .borrow_mut()
+            .push(Box::new(move || start_task(&client, &head)));
+        let (feedback, output) = body(entry, feedback);
+        let tail = feedback.apply(loop_tail());
+        head_tail.on_definition(|consumer| consumer.connect_input(&tail));
+        output.apply(loop_exit(1))
+    }
+}
+
+/// Create a task which merges the events entering a loop with the events which are fed back.
+/// Returns the task, for connecting the feedback later, together with its output stream.
+fn loop_head<T: DataReqs>(
+    entry: Stream<T>,
+    progress: &Arc<Progress>,
+) -> (Arc<Component<Task<(), T, T, Never>>>, Stream<T>) {
+    let task = Task::new(
+        "LoopHead",
+        (),
+        |task: &mut Task<(), T, T, Never>, event: T| {
+            task.emit(event);
+        },
+    );
+    let start_fns = entry.start_fns.clone();
+    let client = entry.client.clone();
+    let task = create_task(&entry.client, task);
+    register_loop(&entry.client, &task);
+    progress.set_head(task.actor_ref());
+    task.on_definition(|consumer| consumer.connect_input(&entry));
+    let stream = Stream::from_task(entry.client.clone(), start_fns.clone(), &task);
+    let head = task.clone();
+    start_fns
+        .borrow_mut()
+        .push(Box::new(move || start_task(&client, &task)));
+    (head, stream)
+}
+
+/// Record a loop head in the logical graph, so that its feedback is validated.
+fn register_loop<S: DataReqs, T: DataReqs>(
+    client: &Arc<Component<Client>>,
+    head: &Arc<Component<Task<S, T, T, Never>>>,
+) {
+    if let Some(node) = head.on_definition(|head| head.node) {
+        client.on_definition(|c| c.graph.loops.push(node));
     }
 }
 
-struct Scope<const A: usize>([i32; A]);
+/// A task which enters events into a loop, with a loop counter of zero.
+fn loop_entry<T: DataReqs>(progress: Arc<Progress>) -> Task<Arc<Progress>, T, T, Never> {
+    Task::new(
+        "LoopEntry",
+        progress,
+        |task: &mut Task<Arc<Progress>, T, T, Never>, event: T| {
+            let progress = task.state.clone();
+            task.scopes.push(Frame {
+                counter: 0,
+                progress,
+            });
+            task.emit(event);
+            task.scopes.pop();
+        },
+    )
+    .on_end(|task| task.state.end_input())
+}
+
+/// A task which increments the loop counter of events which are fed back. Events in transit to
+/// the loop head thereby already belong to the next iteration.
+fn loop_tail<T: DataReqs>() -> Task<(), T, T, Never> {
+    Task::new(
+        "LoopTail",
+        (),
+        |task: &mut Task<(), T, T, Never>, event: T| {
+            task.scopes.last_mut().unwrap().counter += 1;
+            task.emit(event);
+        },
+    )
+}
+
+/// A task which removes the `depth` innermost loop counters of events exiting a loop.
+fn loop_exit<T: DataReqs>(depth: usize) -> Task<usize, T, T, Never> {
+    Task::new(
+        "LoopExit",
+        depth,
+        |task: &mut Task<usize, T, T, Never>, event: T| {
+            let len = task.scopes.len() - task.state;
+            task.scopes.truncate(len);
+            task.emit(event);
+        },
+    )
+}
+
+/// Epochs of the input of a superstep iteration.
+#[derive(Debug, Clone)]
+pub struct Epochs {
+    /// The number of watermarks received so far.
+    epoch: i32,
+    epochs: Arc<Progress>,
+    rounds: Arc<Progress>,
+}
+
+/// A task which enters events into a superstep iteration, with the counter of their epoch and a
+/// round counter of zero. Each watermark starts a new epoch and closes the previous one.
+fn superstep_entry<T: DataReqs>(rounds: Arc<Progress>) -> Task<Epochs, T, T, Never> {
+    let epochs = Epochs {
+        epoch: 0,
+        epochs: Arc::new(Progress::default()),
+        rounds,
+    };
+    Task::new(
+        "SuperstepEntry",
+        epochs,
+        |task: &mut Task<Epochs, T, T, Never>, event: T| {
+            task.scopes.push(Frame {
+                counter: task.state.epoch,
+                progress: task.state.epochs.clone(),
+            });
+            task.scopes.push(Frame {
+                counter: 0,
+                progress: task.state.rounds.clone(),
+            });
+            task.emit(event);
+            let len = task.scopes.len() - 2;
+            task.scopes.truncate(len);
+        },
+    )
+    .on_watermark(|task, _| {
+        task.state.epoch += 1;
+        task.state.rounds.close(task.state.epoch);
+    })
+    .on_end(|task| {
+        task.state.epochs.end_input();
+        task.state.rounds.end_input();
+    })
+}
+
+/// State of the barrier between supersteps.
+#[derive(Debug, Clone)]
+pub struct Superstep<A: DataReqs, T: DataReqs> {
+    init: A,
+    fold: fn(&mut A, &T),
+    converged: fn(&A, i32) -> bool,
+    /// Aggregate and messages of the next round of each epoch, with their loop counters.
+    rounds: HashMap<Vec<i32>, (A, Vec<(Vec<Frame>, T)>)>,
+}
+
+/// Loop counters of the `A` innermost loops which an event is inside, innermost first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scope<const A: usize>(pub [i32; A]);
+
+impl<const A: usize> Scope<A> {
+    fn new(frames: &[Frame]) -> Self {
+        let mut counters = [0; A];
+        for (counter, frame) in counters.iter_mut().zip(frames.iter().rev()) {
+            *counter = frame.counter;
+        }
+        Scope(counters)
+    }
+}
+
+/// The counter of an event in a loop, together with the progress of the loop.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub counter: i32,
+    pub progress: Arc<Progress>,
+}
+
+/// Tracks the events inside a loop.
+///
+/// Every event inside the loop has a pointstamp, which is the counters of the loops it is
+/// inside, outermost first. A pointstamp is counted once for each consumer when an event is
+/// emitted with it, and retired by each consumer when it has processed the event. Since tasks
+/// emit before they retire, the count of events inside the loop only reaches zero when the loop
+/// is quiescent.
+#[derive(Default)]
+pub struct Progress {
+    counts: Mutex<BTreeMap<Vec<i32>, usize>>,
+    input_ended: AtomicBool,
+    /// Epochs below this one have been closed, and no longer receive input. Only set for the
+    /// rounds of a superstep iteration.
+    closed: Mutex<Option<i32>>,
+    head: Mutex<Option<ActorRef<TaskMessage<Never>>>>,
+    /// Tasks waiting for pointstamps to become complete.
+    notifications: Mutex<Vec<(Vec<i32>, ActorRef<TaskMessage<Never>>)>>,
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Progress")
+            .field("counts", &self.counts.lock().unwrap())
+            .finish()
+    }
+}
+
+impl Progress {
+    fn set_head(&self, head: ActorRef<TaskMessage<Never>>) {
+        *self.head.lock().unwrap() = Some(head);
+    }
+
+    fn produce(&self, pointstamp: Vec<i32>, count: usize) {
+        *self.counts.lock().unwrap().entry(pointstamp).or_insert(0) += count;
+    }
+
+    fn retire(&self, pointstamp: Vec<i32>) {
+        let mut counts = self.counts.lock().unwrap();
+        let count = counts
+            .get_mut(&pointstamp)
+            .expect("Retired unknown pointstamp");
+        *count -= 1;
+        if *count == 0 {
+            counts.remove(&pointstamp);
+        }
+        drop(counts);
+        self.check();
+    }
+
+    fn end_input(&self) {
+        self.input_ended.store(true, Ordering::SeqCst);
+        self.check();
+    }
+
+    /// Close all epochs below `epoch`.
+    fn close(&self, epoch: i32) {
+        *self.closed.lock().unwrap() = Some(epoch);
+        self.check();
+    }
+
+    /// Returns true if the epoch which `pointstamp` belongs to is closed.
+    fn is_closed(&self, pointstamp: &[i32]) -> bool {
+        match *self.closed.lock().unwrap() {
+            Some(epoch) => pointstamp.len() >= 2 && pointstamp[pointstamp.len() - 2] < epoch,
+            None => false,
+        }
+    }
+
+    /// Returns true if no event inside the loop has a pointstamp which could result in an event
+    /// with a pointstamp less than or equal to `pointstamp`. Pointstamps are only comparable
+    /// within the same context: P ≥ P′ iff (Pctx = P′ctx) ∧ (PT ≥ P′T).
+    pub fn is_complete(&self, pointstamp: &[i32]) -> bool {
+        let (time, context) = pointstamp.split_last().unwrap();
+        let counts = self.counts.lock().unwrap();
+        counts.keys().all(|other| {
+            let (other_time, other_context) = other.split_last().unwrap();
+            other_context != context || other_time > time
+        })
+    }
+
+    /// Notify a task once the input has ended, or the epoch of `pointstamp` is closed, and
+    /// `pointstamp` is complete.
+    fn notify_at(&self, pointstamp: Vec<i32>, task: ActorRef<TaskMessage<Never>>) {
+        self.notifications.lock().unwrap().push((pointstamp, task));
+        self.check();
+    }
+
+    /// Send notifications for complete pointstamps, and stop the loop head once the input has
+    /// ended and the loop is quiescent.
+    fn check(&self) {
+        let ended = self.input_ended.load(Ordering::SeqCst);
+        let mut notifications = self.notifications.lock().unwrap();
+        let (ready, waiting): (Vec<_>, Vec<_>) =
+            notifications.drain(..).partition(|(pointstamp, _)| {
+                (ended || self.is_closed(pointstamp)) && self.is_complete(pointstamp)
+            });
+        *notifications = waiting;
+        drop(notifications);
+        for (pointstamp, task) in ready {
+            task.tell(TaskMessage::Notify(pointstamp));
+        }
+        if ended && self.counts.lock().unwrap().is_empty() {
+            if let Some(head) = self.head.lock().unwrap().take() {
+                head.tell(TaskMessage::Die);
+            }
+        }
+    }
+}
+
+/// Count an event which is emitted inside loops, once for each consumer which will retire it.
+pub(crate) fn produce(frames: &[Frame], consumers: usize) {
+    if consumers > 0 {
+        for (depth, frame) in frames.iter().enumerate() {
+            frame
+                .progress
+                .produce(pointstamp(&frames[..=depth]), consumers);
+        }
+    }
+}
+
+/// Retire an event which has been processed inside loops.
+pub(crate) fn retire(frames: &[Frame]) {
+    for (depth, frame) in frames.iter().enumerate() {
+        frame.progress.retire(pointstamp(&frames[..=depth]));
+    }
+}
+
+fn pointstamp(frames: &[Frame]) -> Vec<i32> {
+    frames.iter().map(|frame| frame.counter).collect()
+}
@@ -0,0 +1,108 @@
+use kompact::prelude::*;
+
+use crate::data::*;
+use crate::stream::*;
+use crate::task::*;
+
+impl<I: DataReqs> Stream<I> {
+    /// Transform each event of a stream.
+    pub fn map<O: DataReqs>(self, mut f: impl FnMut(I) -> O + Send + 'static) -> Stream<O> {
+        self.apply(Task::new(
+            "Map",
+            (),
+            move |task: &mut Task<(), I, O, Never>, event| task.emit(f(event)),
+        ))
+    }
+
+    /// Keep the events of a stream which satisfy a predicate.
+    pub fn filter(self, mut f: impl FnMut(&I) -> bool + Send + 'static) -> Stream<I> {
+        self.apply(Task::new(
+            "Filter",
+            (),
+            move |task: &mut Task<(), I, I, Never>, event| {
+                if f(&event) {
+                    task.emit(event);
+                }
+            },
+        ))
+    }
+
+    /// Transform each event of a stream into any number of events.
+    pub fn flat_map<O: DataReqs, T: IntoIterator<Item = O>>(
+        self,
+        mut f: impl FnMut(I) -> T + Send + 'static,
+    ) -> Stream<O> {
+        self.apply(Task::new(
+            "FlatMap",
+            (),
+            move |task: &mut Task<(), I, O, Never>, event| {
+                for data in f(event) {
+                    task.emit(data);
+                }
+            },
+        ))
+    }
+
+    /// Transform each event of a stream, and keep the results which are `Some`.
+    pub fn filter_map<O: DataReqs>(
+        self,
+        mut f: impl FnMut(I) -> Option<O> + Send + 'static,
+    ) -> Stream<O> {
+        self.apply(Task::new(
+            "FilterMap",
+            (),
+            move |task: &mut Task<(), I, O, Never>, event| {
+                if let Some(data) = f(event) {
+                    task.emit(data);
+                }
+            },
+        ))
+    }
+
+    /// Call a function with each event of a stream, and pass the event on.
+    pub fn inspect(self, mut f: impl FnMut(&I) + Send + 'static) -> Stream<I> {
+        self.apply(Task::new(
+            "Inspect",
+            (),
+            move |task: &mut Task<(), I, I, Never>, event| {
+                f(&event);
+                task.emit(event);
+            },
+        ))
+    }
+
+    /// Fold the events of a stream into an accumulator, which is emitted when the stream ends.
+    pub fn fold<A: DataReqs>(
+        self,
+        init: A,
+        mut f: impl FnMut(&mut A, I) + Send + 'static,
+    ) -> Stream<A> {
+        self.apply(
+            Task::new(
+                "Fold",
+                init,
+                move |task: &mut Task<A, I, A, Never>, event| f(&mut task.state, event),
+            )
+            .on_end(|task| {
+                let acc = task.state.clone();
+                task.emit(acc);
+            }),
+        )
+    }
+
+    /// Fold the events of a stream into an accumulator, and emit a result for each event.
+    pub fn scan<A: DataReqs, O: DataReqs>(
+        self,
+        init: A,
+        mut f: impl FnMut(&mut A, I) -> O + Send + 'static,
+    ) -> Stream<O> {
+        self.apply(Task::new(
+            "Scan",
+            init,
+            move |task: &mut Task<A, I, O, Never>, event| {
+                let data = f(&mut task.state, event);
+                task.emit(data);
+            },
+        ))
+    }
+}
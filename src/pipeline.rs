@@ -9,9 +9,12 @@ use std::collections::HashMap;
 use std::rc::Rc;
 use std::sync::Arc;
 
+use crate::chain::*;
 use crate::client::*;
 use crate::data::*;
 use crate::executor::*;
+use crate::graph::*;
+use crate::status::*;
 use crate::task::*;
 
 pub(crate) struct Pipeline<S: SystemHandle> {
@@ -20,11 +23,65 @@ pub(crate) struct Pipeline<S: SystemHandle> {
     pub(crate) startup: Rc<RefCell<Vec<Box<dyn FnOnce() + 'static>>>>,
 }
 
+/// A handle to a running pipeline.
+pub struct PipelineHandle {
+    pub(crate) client: Arc<Component<Client>>,
+    pub(crate) monitor: Arc<Monitor>,
+}
+
 impl<S: SystemHandle> Pipeline<S> {
-    pub(crate) fn finalize(self) {
+    /// Returns the logical graph of the pipeline built so far.
+    pub(crate) fn graph(&self) -> Graph {
+        self.client.on_definition(|c| c.graph.clone())
+    }
+
+    /// Validate the pipeline and start all of its tasks.
+    /// Fails without starting any task if the logical graph of the pipeline is invalid.
+    pub(crate) fn finalize(self) -> Result<PipelineHandle, GraphError> {
+        self.graph().validate()?;
+        fuse(&self.client);
         for starter in self.startup.borrow_mut().drain(..).rev() {
             starter();
         }
+        let monitor = self.client.on_definition(|c| c.monitor.clone());
+        Ok(PipelineHandle {
+            client: self.client,
+            monitor,
+        })
+    }
+}
+
+impl PipelineHandle {
+    /// Gracefully stop the pipeline. Sources stop producing and end their streams. Every other
+    /// task drains its input, fires its remaining event-time timers, and terminates when the end
+    /// of its input stream arrives, which means tasks terminate in topological order.
+    pub(crate) fn stop(&self) {
+        self.client.on_definition(|client| {
+            for source in &client.sources {
+                source.tell(TaskMessage::Die);
+            }
+        });
+    }
+
+    /// Immediately stop all tasks of the pipeline.
+    pub(crate) fn cancel(&self) {
+        self.monitor.cancel();
+    }
+
+    /// Block until all tasks of the pipeline have terminated.
+    /// Returns the termination status of each task.
+    pub(crate) fn await_completion(&self) -> Vec<(&'static str, TaskStatus)> {
+        self.monitor.await_completion()
+    }
+
+    /// Returns the logical graph of the pipeline.
+    pub(crate) fn graph(&self) -> Graph {
+        self.client.on_definition(|c| c.graph.clone())
+    }
+
+    /// Returns the current status of each task of the pipeline.
+    pub(crate) fn statuses(&self) -> Vec<(&'static str, TaskStatus)> {
+        self.monitor.statuses()
     }
 }
 
@@ -33,6 +90,8 @@ impl Executor {
         let starters = Rc::new(RefCell::new(Vec::new()));
         let client = self.system.create(Client::new);
         let system = client.on_definition(|c| c.ctx().system());
+        let monitor = client.on_definition(|c| c.monitor.clone());
+        self.pipelines.borrow_mut().push(monitor);
         Pipeline {
             system,
             client,
@@ -45,6 +104,10 @@ impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
     pub(crate) fn pipeline(&self) -> Pipeline<impl SystemHandle> {
         let system = self.ctx.system();
         let client = system.create(Client::new);
+        if let Some(reporter) = self.reporter.as_ref() {
+            let monitor = client.on_definition(|c| c.monitor.clone());
+            reporter.adopt(monitor);
+        }
         let starters = Rc::new(RefCell::new(Vec::new()));
         Pipeline {
             system,
@@ -1,7 +1,12 @@
 use kompact::prelude::*;
 use std::marker::PhantomData;
+use uuid::Uuid;
 
+use crate::batch::*;
+use crate::control::*;
 use crate::data::*;
+use crate::loops::*;
+use crate::side::*;
 
 /// A port for transferring data.
 #[derive(Debug)]
@@ -15,16 +20,27 @@ impl<T: DataReqs> Port for DataPort<T> {
 /// An event which may arrive on a DataPort.
 #[derive(Debug, Clone)]
 pub enum DataEvent<T: DataReqs> {
-    Watermark(DateTime),
+    /// Producer with the given id will not send any more items older than the given time.
+    Watermark(Uuid, DateTime),
     Item(DateTime, T),
-    End,
+    /// An item inside one or more loops.
+    Scoped(Vec<Frame>, DateTime, T),
+    Batch(Batch<T>),
+    /// An event which is only sent to the consumers of the given routes, as a bitmask. It is
+    /// buffered by its producer, which unwraps it when sending it.
+    Routed(u64, Box<DataEvent<T>>),
+    /// Producer with the given id has ended its stream.
+    End(Uuid),
 }
 
+/// A reply which may be sent by a consumer to its producers on a DataPort.
 #[derive(Debug, Clone)]
 pub enum DataReply {
-    Pull,
+    /// Consumer with the given id grants credit for sending a number of events.
+    Pull(Uuid, usize),
 }
 
+/// A port for sending control-events from consumers to their producers.
 #[derive(Debug)]
 pub struct CtrlPort;
 
@@ -33,8 +49,37 @@ impl Port for CtrlPort {
     type Request = CtrlEvent;
 }
 
+/// A control-event which may be sent upstream on a CtrlPort.
 #[derive(Debug, Clone)]
-pub enum CtrlEvent {}
+pub enum CtrlEvent {
+    /// Consumer with the given id asks its producers to stop producing output until resumed.
+    Pause(Uuid),
+    /// Consumer with the given id asks its producers to resume producing output.
+    Resume(Uuid),
+    /// Consumer with the given id asks its producers to send all buffered output.
+    Flush(Uuid),
+    /// Update the configuration of the first upstream task which accepts it.
+    Reconfigure(Command),
+    /// A user-defined command.
+    Command(Command),
+}
 
 #[derive(Debug, Clone)]
 pub enum CtrlReply {}
+
+/// A port for transferring the events of the side outputs of a task to the tasks which produce
+/// their streams.
+#[derive(Debug)]
+pub struct SidePort;
+
+impl Port for SidePort {
+    type Indication = DataReply;
+    type Request = SideEvent;
+}
+
+/// An event of the side output with the given name.
+#[derive(Debug, Clone)]
+pub struct SideEvent {
+    pub tag: &'static str,
+    pub event: DataEvent<SideData>,
+}
@@ -0,0 +1,170 @@
+#![allow(clippy::type_complexity)]
+
+use kompact::prelude::*;
+use uuid::Uuid;
+
+use std::any::type_name;
+use std::sync::Arc;
+
+use crate::data::*;
+use crate::graph::*;
+use crate::port::*;
+use crate::status::*;
+use crate::stream::*;
+use crate::task::*;
+
+/// Maximum number of outputs a stream can be routed to.
+pub const MAX_ROUTES: usize = 64;
+
+/// An output of a task which only receives the events that are routed to it. Its consumers grant
+/// credit through the output port of the task, like all other consumers.
+pub struct Route<O: DataReqs> {
+    pub port: RequiredPort<DataPort<O>>,
+    /// Ids of the consumers of the route.
+    pub consumers: Vec<Uuid>,
+}
+
+impl<I: DataReqs> Stream<I> {
+    /// Route each event of a stream to one of `n` output streams, chosen by its index.
+    pub(crate) fn route(
+        self,
+        n: usize,
+        router: impl Fn(&I) -> usize + Send + 'static,
+    ) -> Vec<Stream<I>> {
+        self.route_many(n, move |event| Some(router(event)))
+    }
+
+    /// Route each event of a stream to any number of `n` output streams, chosen by their
+    /// indices. Each event is only sent to the consumers of the chosen output streams.
+    /// Every output stream is a node of its own in the logical graph, so that an output stream
+    /// which is never consumed is reported as dangling.
+    pub(crate) fn route_many<T: IntoIterator<Item = usize>>(
+        self,
+        n: usize,
+        router: impl Fn(&I) -> T + Send + 'static,
+    ) -> Vec<Stream<I>> {
+        assert!(
+            n <= MAX_ROUTES,
+            "A stream can be routed to at most {} outputs",
+            MAX_ROUTES
+        );
+        let mut task = Task::new(
+            "Router",
+            (),
+            move |task: &mut Task<(), I, I, Never>, event: I| {
+                let routes = router(&event).into_iter().fold(0u64, |routes, index| {
+                    assert!(index < n, "Route {} is out of bounds", index);
+                    routes | 1 << index
+                });
+                if routes != 0 {
+                    task.emit_routed(routes, event);
+                }
+            },
+        )
+        // Routed events are only delivered through the ports of their routes
+        .disable_chaining();
+        task.routes = (0..n)
+            .map(|_| Route {
+                port: RequiredPort::uninitialised(),
+                consumers: Vec::new(),
+            })
+            .collect();
+        let task = create_task(&self.client, task);
+        task.on_definition(|c| c.connect_input(&self));
+        let router = task.on_definition(|c| c.node);
+        let client = self.client.clone();
+        let started = task.clone();
+        self.start_fns
+            .borrow_mut()
+            .push(Box::new(move || start_task(&client, &started)));
+        (0..n)
+            .map(|index| {
+                let node = self.client.on_definition(|c| {
+                    let node =
+                        c.graph
+                            .add_node("Route", type_name::<I>(), type_name::<I>(), false, false);
+                    if let Some(router) = router {
+                        c.graph.add_edge(router, node);
+                    }
+                    node
+                });
+                Stream::new(
+                    self.client.clone(),
+                    route_connector(task.clone(), index),
+                    self.start_fns.clone(),
+                    Some(task.actor_ref().recipient()),
+                    Some(node),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Connects a consumer to a route of a producer. Events are only sent to the consumer through the
+/// route, while its credit is granted through the output port of the producer.
+fn route_connector<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs>(
+    producer: Arc<Component<Task<S, I, O, R>>>,
+    index: usize,
+) -> Arc<ConnectFn<O>> {
+    Arc::new(move |id, iport, oport| {
+        producer.on_definition(|producer| {
+            iport.connect(producer.data_oport.share());
+            let route = &mut producer.routes[index];
+            route.port.connect(iport.share());
+            route.consumers.push(id);
+            oport.connect(producer.ctrl_iport.share());
+            producer.ctrl_iport.connect(oport.share());
+            producer.buffer.consumers += 1;
+        })
+    })
+}
+
+impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
+    /// Emit an event only to the consumers of the given routes, as a bitmask.
+    pub(crate) fn emit_routed(&mut self, routes: u64, data: O) {
+        self.routing = Some(routes);
+        self.emit(data);
+        self.routing = None;
+    }
+
+    /// Returns the number of consumers which the events that are currently emitted are sent to.
+    pub(crate) fn recipients(&self) -> usize {
+        match self.routing {
+            Some(routes) => self.routed(routes).count(),
+            None => self.buffer.consumers,
+        }
+    }
+
+    /// Returns the ids of the consumers of the given routes, as a bitmask.
+    pub(crate) fn routed(&self, routes: u64) -> impl Iterator<Item = &Uuid> {
+        self.routes
+            .iter()
+            .enumerate()
+            .filter(move |(index, _)| routes & 1 << index != 0)
+            .flat_map(|(_, route)| route.consumers.iter())
+    }
+
+    /// Send an event on the output port and the routes of the task. A routed event is only sent
+    /// on its routes.
+    pub(crate) fn deliver(&mut self, routes: Option<u64>, event: DataEvent<O>) {
+        for (index, route) in self.routes.iter_mut().enumerate() {
+            if routes.map_or(true, |routes| routes & 1 << index != 0) {
+                route.port.trigger(event.clone());
+            }
+        }
+        if routes.is_none() {
+            match self.chained.as_mut() {
+                Some(chained) => chained(event),
+                None => self.data_oport.trigger(event),
+            }
+        }
+    }
+}
+
+/// Wrap an event which is sent to the given routes, as a bitmask, if any.
+pub(crate) fn routed<T: DataReqs>(routes: Option<u64>, event: DataEvent<T>) -> DataEvent<T> {
+    match routes {
+        Some(routes) => DataEvent::Routed(routes, Box::new(event)),
+        None => event,
+    }
+}
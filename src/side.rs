@@ -0,0 +1,307 @@
+#![allow(clippy::type_complexity)]
+
+use kompact::prelude::*;
+use uuid::Uuid;
+
+use std::any::Any;
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::client::*;
+use crate::data::*;
+use crate::graph::*;
+use crate::loops::*;
+use crate::port::*;
+use crate::status::*;
+use crate::stream::*;
+use crate::task::*;
+
+/// Identifies a side output of type `T`.
+#[derive(Debug)]
+pub struct Tag<T> {
+    pub name: &'static str,
+    marker: PhantomData<T>,
+}
+
+impl<T> Tag<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for Tag<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Tag<T> {}
+
+/// Creates the task of a side output once the task which declares it has been created, and
+/// connects it to the side port of that task.
+pub type SideFn =
+    Box<dyn FnOnce(&Arc<Component<Client>>, &mut RequiredPort<SidePort>) -> SideOutput + Send>;
+
+/// Data of a side output, whose type is erased so that all side outputs of a task can share a
+/// port.
+#[derive(Clone)]
+pub struct SideData(Arc<dyn Any + Send + Sync>);
+
+impl fmt::Debug for SideData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SideData(..)")
+    }
+}
+
+impl SideData {
+    fn downcast<T: DataReqs>(&self) -> T {
+        self.0
+            .downcast_ref::<T>()
+            .cloned()
+            .expect("Side output has an unexpected type")
+    }
+}
+
+/// A side output of a task. Its events are buffered until the task which produces its stream
+/// has granted credit for them, in the same way as the events of the output port.
+pub struct SideOutput {
+    tag: &'static str,
+    data_type: TypeId,
+    /// Id of the task which produces the stream of the side output.
+    id: Uuid,
+    node: Option<NodeId>,
+    credit: usize,
+    queue: VecDeque<DataEvent<SideData>>,
+    ended: bool,
+    /// Creates the stream of the side output.
+    stream: Box<dyn Fn(&Arc<Component<Client>>, &Rc<RefCell<Vec<ErasedFn>>>) -> Rc<dyn Any> + Send>,
+    /// Starts the task which produces the stream of the side output.
+    start: Option<Box<dyn FnOnce(&Arc<Component<Client>>) + Send>>,
+}
+
+impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
+    /// Declare a side output, whose stream is returned by `Stream::side_output` on the output
+    /// stream of the task.
+    pub fn side_output<T: DataReqs>(mut self, tag: Tag<T>) -> Self {
+        self.side_fns.push((
+            tag.name,
+            Box::new(
+                move |client: &Arc<Component<Client>>, oport: &mut RequiredPort<SidePort>| {
+                    // The task of a side output is named after its tag, which it uses to pick out
+                    // its own events from the side port
+                    let task = create_task(
+                        client,
+                        Task::new(tag.name, (), |task: &mut Task<(), T, T, Never>, event| {
+                            task.emit(event)
+                        }),
+                    );
+                    let (id, node) = task.on_definition(|task| {
+                        // The side output ends once the task which declares it ends
+                        task.buffer.producers = 1;
+                        task.side_iport.connect(oport.share());
+                        oport.connect(task.side_iport.share());
+                        (task.ctx.id(), task.node)
+                    });
+                    let producer = task.clone();
+                    SideOutput {
+                        tag: tag.name,
+                        data_type: TypeId::of::<T>(),
+                        id,
+                        node,
+                        credit: 0,
+                        queue: VecDeque::new(),
+                        ended: false,
+                        stream: Box::new(
+                            move |client: &Arc<Component<Client>>, start_fns: &Rc<_>| {
+                                let stream =
+                                    Stream::from_task(client.clone(), start_fns.clone(), &producer);
+                                Rc::new(stream) as Rc<dyn Any>
+                            },
+                        ),
+                        start: Some(Box::new(move |client: &Arc<Component<Client>>| {
+                            start_task(client, &task)
+                        })),
+                    }
+                },
+            ),
+        ));
+        self
+    }
+
+    /// Emit an event to a side output.
+    pub fn emit_to<T: DataReqs>(&mut self, tag: &Tag<T>, data: T) {
+        let name = self.name;
+        let side = self
+            .side_outputs
+            .get_mut(tag.name)
+            .filter(|side| side.data_type == TypeId::of::<T>())
+            .unwrap_or_else(|| panic!("Task {} has no side output {}", name, tag.name));
+        let data = SideData(Arc::new(data));
+        let event = if self.scopes.is_empty() {
+            DataEvent::Item(self.event_time, data)
+        } else {
+            // The task of the side output retires the event once it has processed it
+            produce(&self.scopes, 1);
+            DataEvent::Scoped(self.scopes.clone(), self.event_time, data)
+        };
+        side.queue.push_back(event);
+        self.flush_side_outputs();
+    }
+
+    /// Send buffered events of the side outputs while their tasks have credit.
+    pub(crate) fn flush_side_outputs(&mut self) {
+        let congested = self.is_congested();
+        for side in self.side_outputs.values_mut() {
+            while side.credit > 0 {
+                match side.queue.pop_front() {
+                    Some(event) => {
+                        side.credit -= 1;
+                        let tag = side.tag;
+                        self.side_oport.trigger(SideEvent { tag, event });
+                    }
+                    None => break,
+                }
+            }
+        }
+        if self.buffer.terminating && self.buffer.queue.is_empty() && self.side_backlog() == 0 {
+            self.ctx.suicide();
+        } else if congested && !self.is_congested() {
+            self.grant();
+            self.resume_timer();
+        }
+    }
+
+    /// Number of events of side outputs which have not yet been sent.
+    pub(crate) fn side_backlog(&self) -> usize {
+        self.side_outputs
+            .values()
+            .map(|side| side.queue.len())
+            .sum()
+    }
+
+    /// End the streams of the side outputs, after their buffered events.
+    pub(crate) fn end_side_outputs(&mut self) {
+        let id = self.ctx.id();
+        for side in self.side_outputs.values_mut().filter(|side| !side.ended) {
+            side.ended = true;
+            side.queue.push_back(DataEvent::End(id));
+        }
+        self.flush_side_outputs();
+    }
+
+    /// End the streams of the side outputs immediately, when the task fails or is killed. The
+    /// events of the side outputs which have not yet been sent are lost.
+    pub(crate) fn abort_side_outputs(&mut self) {
+        let id = self.ctx.id();
+        for side in self.side_outputs.values_mut() {
+            side.queue.clear();
+            if !side.ended {
+                side.ended = true;
+                let tag = side.tag;
+                self.side_oport.trigger(SideEvent {
+                    tag,
+                    event: DataEvent::End(id),
+                });
+            }
+        }
+    }
+}
+
+/// Create the tasks of the side outputs of a task, and record them in the logical graph.
+pub(crate) fn create_side_outputs<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs>(
+    client: &Arc<Component<Client>>,
+    task: &Arc<Component<Task<S, I, O, R>>>,
+    side_fns: Vec<(&'static str, SideFn)>,
+) {
+    task.on_definition(|task| {
+        for (name, side_fn) in side_fns {
+            let side = side_fn(client, &mut task.side_oport);
+            if let (Some(from), Some(to)) = (task.node, side.node) {
+                client.on_definition(|c| c.graph.add_edge(from, to));
+            }
+            task.side_outputs.insert(name, side);
+        }
+    });
+}
+
+/// Start the tasks of the side outputs of a task.
+pub(crate) fn start_side_outputs<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs>(
+    client: &Arc<Component<Client>>,
+    task: &Arc<Component<Task<S, I, O, R>>>,
+) {
+    let starts = task.on_definition(|task| {
+        task.side_outputs
+            .values_mut()
+            .filter_map(|side| side.start.take())
+            .collect::<Vec<_>>()
+    });
+    for start in starts {
+        start(client);
+    }
+}
+
+/// Returns the streams of the side outputs of a task.
+pub(crate) fn side_streams<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs>(
+    client: &Arc<Component<Client>>,
+    start_fns: &Rc<RefCell<Vec<ErasedFn>>>,
+    task: &Arc<Component<Task<S, I, O, R>>>,
+) -> HashMap<&'static str, Rc<dyn Any>> {
+    task.on_definition(|task| {
+        task.side_outputs
+            .iter()
+            .map(|(name, side)| (*name, (side.stream)(client, start_fns)))
+            .collect()
+    })
+}
+
+impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Provide<SidePort> for Task<S, I, O, R> {
+    fn handle(&mut self, event: SideEvent) -> Handled {
+        // The side port is shared by all side outputs of the producer
+        if event.tag != self.name {
+            return Handled::Ok;
+        }
+        let event = match event.event {
+            DataEvent::Item(time, data) => DataEvent::Item(time, data.downcast()),
+            DataEvent::Scoped(scopes, time, data) => {
+                DataEvent::Scoped(scopes, time, data.downcast())
+            }
+            DataEvent::End(id) => DataEvent::End(id),
+            event => unreachable!("Unexpected side output event {:?}", event),
+        };
+        self.guard(|task| task.handle_data(event))
+    }
+}
+
+impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Require<SidePort> for Task<S, I, O, R> {
+    fn handle(&mut self, event: DataReply) -> Handled {
+        match event {
+            DataReply::Pull(id, amount) => {
+                if let Some(side) = self.side_outputs.values_mut().find(|side| side.id == id) {
+                    side.credit += amount;
+                }
+                self.flush_side_outputs();
+            }
+        }
+        Handled::Ok
+    }
+}
+
+impl<T: DataReqs> Stream<T> {
+    /// Returns the stream of a side output of the task which produces this stream.
+    pub fn side_output<X: DataReqs>(&self, tag: &Tag<X>) -> Stream<X> {
+        self.sides
+            .get(tag.name)
+            .and_then(|stream| stream.downcast_ref::<Stream<X>>())
+            .cloned()
+            .unwrap_or_else(|| panic!("Stream has no side output {}", tag.name))
+    }
+}
@@ -27,4 +27,19 @@ impl<I: DataReqs> Stream<I> {
             startup: stream.start_fns,
         }
     }
+
+    /// Write a stream to a sink.
+    /// Also returns a future which is completed with the value the sink passes to `exit`.
+    pub(crate) fn sink_terminating<S: DataReqs, R: DataReqs>(
+        self,
+        task: Task<S, I, Never, R>,
+    ) -> (Pipeline<impl SystemHandle>, KFuture<R>) {
+        let (stream, result) = self.apply_terminating(task);
+        let pipeline = Pipeline {
+            system: stream.client.on_definition(|c| c.ctx().system()),
+            client: stream.client,
+            startup: stream.start_fns,
+        };
+        (pipeline, result)
+    }
 }
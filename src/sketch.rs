@@ -0,0 +1,349 @@
+use kompact::prelude::*;
+
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::data::*;
+use crate::keyed::*;
+use crate::stream::*;
+use crate::task::*;
+use crate::window::*;
+
+/// An approximate aggregate of values of type `T`.
+///
+/// The states of sketches with the same parameters can be merged, so a sketch can be computed
+/// by parallel instances and combined afterwards.
+pub trait Sketch<T>: DataReqs {
+    /// Add a value to the sketch.
+    fn insert(&mut self, value: &T);
+    /// Add the values of another sketch to this one.
+    fn merge(&mut self, other: &Self);
+}
+
+/// A 64-bit FNV-1a hasher. Unlike `DefaultHasher`, it hashes values the same way in every
+/// process, so sketches which were computed by other instances or restored from a checkpoint
+/// can be merged.
+struct Fnv(u64);
+
+impl Fnv {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+}
+
+impl Hasher for Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    /// Mix the state, so that the high bits of the hash depend on every byte of the value.
+    fn finish(&self) -> u64 {
+        let mut hash = self.0;
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+        hash ^ hash >> 33
+    }
+}
+
+/// Hash a value together with a seed.
+fn hash<T: Hash>(value: &T, seed: u64) -> u64 {
+    let mut hasher = Fnv(Fnv::OFFSET);
+    seed.hash(&mut hasher);
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Estimates the number of distinct values.
+#[derive(Debug, Clone)]
+pub struct HyperLogLog<T> {
+    precision: u32,
+    /// Highest rank observed for each register.
+    registers: Vec<u8>,
+    marker: PhantomData<fn(&T)>,
+}
+
+impl<T> HyperLogLog<T> {
+    /// Create a sketch with `2^precision` registers. The standard error of the estimate is
+    /// roughly `1.04 / sqrt(2^precision)`.
+    pub fn new(precision: u32) -> Self {
+        assert!(
+            (4..=16).contains(&precision),
+            "Precision must be between 4 and 16"
+        );
+        Self {
+            precision,
+            registers: vec![0; 1 << precision],
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the estimated number of distinct values.
+    pub fn count(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum = self
+            .registers
+            .iter()
+            .map(|rank| 2f64.powi(-(*rank as i32)))
+            .sum::<f64>();
+        let estimate = alpha * m * m / sum;
+        let zeros = self.registers.iter().filter(|rank| **rank == 0).count();
+        // Use linear counting for small cardinalities
+        if estimate <= 2.5 * m && zeros > 0 {
+            (m * (m / zeros as f64).ln()).round() as u64
+        } else {
+            estimate.round() as u64
+        }
+    }
+}
+
+impl<T: DataReqs + Hash> Sketch<T> for HyperLogLog<T> {
+    fn insert(&mut self, value: &T) {
+        let hash = hash(value, 0);
+        let index = (hash >> (64 - self.precision)) as usize;
+        let rank = ((hash << self.precision) | (1 << (self.precision - 1))).leading_zeros() + 1;
+        self.registers[index] = self.registers[index].max(rank as u8);
+    }
+
+    fn merge(&mut self, other: &Self) {
+        assert_eq!(self.precision, other.precision, "Precisions must be equal");
+        for (rank, other) in self.registers.iter_mut().zip(&other.registers) {
+            *rank = (*rank).max(*other);
+        }
+    }
+}
+
+/// Estimates the frequency of each value. Estimates are never lower than the true frequency.
+#[derive(Debug, Clone)]
+pub struct CountMin<T> {
+    width: usize,
+    depth: usize,
+    /// One row of counters for each hash function.
+    counters: Vec<u64>,
+    marker: PhantomData<fn(&T)>,
+}
+
+impl<T: Hash> CountMin<T> {
+    /// Create a sketch with `depth` rows of `width` counters. Estimates exceed the true
+    /// frequency by at most `2 / width` of the total count with probability `1 - 1 / 2^depth`.
+    pub fn new(width: usize, depth: usize) -> Self {
+        assert!(width > 0 && depth > 0, "Width and depth must be positive");
+        Self {
+            width,
+            depth,
+            counters: vec![0; width * depth],
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the estimated frequency of a value.
+    pub fn frequency(&self, value: &T) -> u64 {
+        (0..self.depth)
+            .map(|row| self.counters[self.index(row, value)])
+            .min()
+            .unwrap()
+    }
+
+    fn index(&self, row: usize, value: &T) -> usize {
+        row * self.width + (hash(value, row as u64) % self.width as u64) as usize
+    }
+}
+
+impl<T: DataReqs + Hash> Sketch<T> for CountMin<T> {
+    fn insert(&mut self, value: &T) {
+        for row in 0..self.depth {
+            let index = self.index(row, value);
+            self.counters[index] += 1;
+        }
+    }
+
+    fn merge(&mut self, other: &Self) {
+        assert!(
+            self.width == other.width && self.depth == other.depth,
+            "Dimensions must be equal"
+        );
+        for (counter, other) in self.counters.iter_mut().zip(&other.counters) {
+            *counter += other;
+        }
+    }
+}
+
+/// Estimates quantiles, with higher accuracy towards the extreme quantiles.
+#[derive(Debug, Clone)]
+pub struct TDigest<T> {
+    compression: f64,
+    /// Mean and weight of each centroid, ordered by mean.
+    centroids: Vec<(f64, f64)>,
+    /// Weighted values which have not yet been merged into the centroids.
+    buffer: Vec<(f64, f64)>,
+    min: f64,
+    max: f64,
+    marker: PhantomData<fn(&T)>,
+}
+
+impl<T> TDigest<T> {
+    /// Create a sketch where a higher `compression` gives more centroids and more accurate
+    /// estimates.
+    pub fn new(compression: f64) -> Self {
+        assert!(compression > 0.0, "Compression must be positive");
+        Self {
+            compression,
+            centroids: Vec::new(),
+            buffer: Vec::new(),
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the estimated value at quantile `q`, or `None` if the sketch is empty.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        let mut centroids = self.centroids.clone();
+        if !self.buffer.is_empty() {
+            centroids = compress(centroids, &self.buffer, self.compression);
+        }
+        if centroids.is_empty() {
+            return None;
+        }
+        let total = centroids.iter().map(|(_, weight)| weight).sum::<f64>();
+        // Interpolate between the centers of the centroids around the target weight
+        let target = q.max(0.0).min(1.0) * total;
+        let (mut previous_mean, mut previous_center) = (self.min, 0.0);
+        let mut cumulative = 0.0;
+        for (mean, weight) in centroids {
+            let center = cumulative + weight / 2.0;
+            if target < center {
+                let fraction = (target - previous_center) / (center - previous_center);
+                return Some(previous_mean + fraction * (mean - previous_mean));
+            }
+            previous_mean = mean;
+            previous_center = center;
+            cumulative += weight;
+        }
+        let fraction = (target - previous_center) / (total - previous_center).max(f64::EPSILON);
+        Some(previous_mean + fraction.min(1.0) * (self.max - previous_mean))
+    }
+
+    /// Merge the buffered values into the centroids.
+    fn flush(&mut self) {
+        let centroids = std::mem::take(&mut self.centroids);
+        self.centroids = compress(centroids, &self.buffer, self.compression);
+        self.buffer.clear();
+    }
+}
+
+/// Merge weighted values into centroids. Centroids near the extreme quantiles are kept small, so
+/// that the extreme quantiles stay accurate.
+fn compress(
+    mut points: Vec<(f64, f64)>,
+    buffer: &[(f64, f64)],
+    compression: f64,
+) -> Vec<(f64, f64)> {
+    points.extend(buffer);
+    // Values are never NaN, since they are skipped when inserted
+    points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    let total = points.iter().map(|(_, weight)| weight).sum::<f64>();
+    let mut centroids = Vec::new();
+    let mut points = points.into_iter();
+    let mut current = match points.next() {
+        Some(point) => point,
+        None => return centroids,
+    };
+    let mut before = 0.0;
+    for (mean, weight) in points {
+        let q = (before + (current.1 + weight) / 2.0) / total;
+        let limit = (4.0 * total * q * (1.0 - q) / compression).max(1.0);
+        if current.1 + weight <= limit {
+            current.0 += (mean - current.0) * weight / (current.1 + weight);
+            current.1 += weight;
+        } else {
+            before += current.1;
+            centroids.push(current);
+            current = (mean, weight);
+        }
+    }
+    centroids.push(current);
+    centroids
+}
+
+impl<T: DataReqs + Into<f64>> Sketch<T> for TDigest<T> {
+    fn insert(&mut self, value: &T) {
+        let value = value.clone().into();
+        // NaN has no place among the quantiles
+        if value.is_nan() {
+            return;
+        }
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.buffer.push((value, 1.0));
+        if self.buffer.len() as f64 >= self.compression * 4.0 {
+            self.flush();
+        }
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.buffer.extend(&other.buffer);
+        self.buffer.extend(&other.centroids);
+        self.flush();
+    }
+}
+
+impl<K: DataReqs + Hash + Eq, T: DataReqs> KeyedStream<K, T> {
+    /// Aggregate the events of each key into a sketch, and emit the result of querying the
+    /// updated sketch of the key for each event.
+    pub fn sketch<S: Sketch<T>, O: DataReqs>(
+        self,
+        sketch: S,
+        query: impl Fn(&S) -> O + Send + 'static,
+    ) -> Stream<(K, O)> {
+        self.aggregate(
+            "Sketch",
+            move |event| {
+                let mut sketch = sketch.clone();
+                sketch.insert(&event);
+                sketch
+            },
+            |sketch, event| sketch.insert(&event),
+            query,
+        )
+    }
+}
+
+impl<T: DataReqs> Stream<T> {
+    /// Aggregate the events of each tumbling event-time window of `size` into a sketch, and emit
+    /// the result of querying the sketch when the window's timer fires.
+    pub fn window_sketch<S: Sketch<T>, O: DataReqs>(
+        self,
+        size: Duration,
+        sketch: S,
+        query: impl Fn(&S) -> O + Send + Sync + 'static,
+    ) -> Stream<O> {
+        let query = Arc::new(query);
+        self.apply(Task::new(
+            "WindowSketch",
+            Window::new(size, sketch),
+            move |task: &mut Task<Window<S>, T, O, Never>, event: T| {
+                let query = query.clone();
+                assign(
+                    task,
+                    |sketch| sketch.insert(&event),
+                    move |task, sketch| task.emit(query(&sketch)),
+                );
+            },
+        ))
+    }
+}
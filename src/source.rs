@@ -1,8 +1,12 @@
+#![allow(deprecated)]
+
 // Window operator can forward
 // Watermark emitted after trigger
 use crate::control::*;
 use kompact::prelude::*;
 use std::collections::HashMap;
+use std::fmt;
+use std::iter::Peekable;
 use std::sync::Arc;
 use std::time::Duration;
 use time::PrimitiveDateTime as DateTime;
@@ -11,40 +15,191 @@ use crate::client::*;
 use crate::data::*;
 use crate::pipeline::*;
 use crate::port::*;
+use crate::status::*;
 use crate::stream::*;
 use crate::task::*;
 
+/// Determines the rate at which a source emits its elements.
+#[derive(Debug, Clone, Copy)]
+pub enum Pacing {
+    /// Emit one element per period.
+    Periodic(Duration),
+    /// Emit elements as fast as the consumers allow.
+    Unbounded,
+    /// Emit `per_second` elements per second on average, in bursts of `burst` elements.
+    Rate { per_second: u64, burst: usize },
+    /// Reproduce the gaps between the event times of elements, scaled by `speed`.
+    /// For example, a speed of `2.0` replays the stream twice as fast as it happened.
+    Replay { speed: f64 },
+}
+
+impl From<Duration> for Pacing {
+    fn from(duration: Duration) -> Self {
+        Pacing::Periodic(duration)
+    }
+}
+
+/// An error in the pacing of a source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PacingError {
+    /// A rate of zero elements per second.
+    ZeroRate,
+    /// A burst of zero elements.
+    ZeroBurst,
+    /// A replay speed which is not positive.
+    InvalidSpeed(f64),
+}
+
+impl fmt::Display for PacingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PacingError::ZeroRate => write!(f, "The rate of a source must be positive"),
+            PacingError::ZeroBurst => write!(f, "The burst of a source must be positive"),
+            PacingError::InvalidSpeed(speed) => {
+                write!(
+                    f,
+                    "The replay speed of a source must be positive, not {}",
+                    speed
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for PacingError {}
+
+impl Pacing {
+    /// Returns an error if the pacing can never emit an element.
+    pub fn validate(&self) -> Result<(), PacingError> {
+        match *self {
+            Pacing::Rate { per_second: 0, .. } => Err(PacingError::ZeroRate),
+            Pacing::Rate { burst: 0, .. } => Err(PacingError::ZeroBurst),
+            // Also rejects NaN, which is not greater than zero
+            Pacing::Replay { speed } if !(speed > 0.0) => Err(PacingError::InvalidSpeed(speed)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Delay before the first element is emitted.
+    fn initial_delay(&self) -> Duration {
+        match *self {
+            Pacing::Periodic(duration) => duration,
+            Pacing::Rate { per_second, burst } => {
+                Duration::from_secs_f64(burst as f64 / per_second as f64)
+            }
+            Pacing::Unbounded | Pacing::Replay { .. } => Duration::from_millis(0),
+        }
+    }
+}
+
+/// An iterator which assigns increasing event times to the elements of another iterator, one
+/// millisecond apart, starting one millisecond after the unix epoch.
+#[derive(Debug, Clone)]
+pub struct Timestamped<T> {
+    iter: T,
+    time: DateTime,
+}
+
+/// Assign increasing event times to the elements of an iterator, so that it can be used as the
+/// input of a source.
+pub fn timestamped<T: IntoIterator>(iter: T) -> Timestamped<T::IntoIter> {
+    Timestamped {
+        iter: iter.into_iter(),
+        time: DateTime::unix_epoch(),
+    }
+}
+
+impl<T: Iterator> Iterator for Timestamped<T> {
+    type Item = (DateTime, T::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let data = self.iter.next()?;
+        self.time += Duration::from_millis(1);
+        Some((self.time, data))
+    }
+}
+
+/// State of a source task.
+#[derive(Debug, Clone)]
+pub struct SourceState<T: Iterator> {
+    iter: Peekable<T>,
+    pacing: Pacing,
+    /// Time of the latest emitted element, elements which are not later than it are discarded.
+    latest: Option<DateTime>,
+}
+
 impl<S: SystemHandle> Pipeline<S> {
-    pub(crate) fn source<T, O: DataReqs>(&self, iter: T, duration: Duration) -> Stream<O>
+    /// Create a stream of timestamped elements, which are emitted at the rate of `pacing`.
+    /// A watermark with the time of the latest element follows each burst of elements.
+    /// Fails if the pacing can never emit an element.
+    pub(crate) fn source<T, O: DataReqs>(
+        &self,
+        iter: T,
+        pacing: impl Into<Pacing>,
+    ) -> Result<Stream<O>, PacingError>
     where
         T: IntoIterator<Item = (DateTime, O)>,
         <T as IntoIterator>::IntoIter: DataReqs,
     {
-        let iter = iter.into_iter();
+        let pacing = pacing.into();
+        pacing.validate()?;
+        let state = SourceState {
+            iter: iter.into_iter().peekable(),
+            pacing,
+            latest: None,
+        };
         let task = Task::new_periodic(
             "Source",
-            iter,
-            duration,
-            |task: &mut Task<<T as IntoIterator>::IntoIter, Never, O, ()>| {
-                if let Some((time, data)) = task.state.next() {
-                    if time > task.lowest_observed_watermarks[0] {
-                        task.lowest_observed_watermarks[0] = time;
-                        task.data_oport.trigger(DataEvent::Item(time, data));
+            state,
+            pacing.initial_delay(),
+            |task: &mut Task<SourceState<<T as IntoIterator>::IntoIter>, Never, O, ()>| {
+                let burst = match task.state.pacing {
+                    Pacing::Periodic(_) | Pacing::Replay { .. } => 1,
+                    Pacing::Rate { burst, .. } => burst,
+                    Pacing::Unbounded => task.buffer.capacity,
+                };
+                let previous = task.state.latest;
+                for _ in 0..burst {
+                    if task.is_congested() {
+                        break;
+                    }
+                    if let Some((time, data)) = task.state.iter.next() {
+                        if task.state.latest.map_or(true, |latest| time > latest) {
+                            task.state.latest = Some(time);
+                            task.send(DataEvent::Item(time, data));
+                        } else {
+                            info!(task.ctx.log(), "Discarded late event with time {}", time);
+                        }
                     } else {
-                        info!(task.ctx.log(), "Discarded late event with time {}", time);
+                        task.exit(());
+                        return;
                     }
-                } else {
-                    task.exit(());
+                }
+                // Elements are emitted in order of time, so none will be older than the latest
+                if task.state.latest > previous {
+                    let latest = task.state.latest.unwrap();
+                    task.send_watermark(latest);
+                }
+                if let (Pacing::Replay { speed }, Some(previous)) =
+                    (task.state.pacing, task.state.latest)
+                {
+                    let gap = match task.state.iter.peek() {
+                        Some((time, _)) => (*time - previous).to_std().unwrap_or_default(),
+                        None => Duration::from_millis(0),
+                    };
+                    task.set_period(Duration::from_secs_f64(gap.as_secs_f64() / speed));
                 }
             },
         )
         .set_role(Role::Producer);
-        let task = self.system.create(move || task);
-        let connect = create_connector(task.clone());
+        let task = create_task(&self.client, task);
+        let stream = Stream::from_task(self.client.clone(), self.startup.clone(), &task);
+        let source = task.actor_ref();
+        self.client.on_definition(|c| c.sources.push(source));
         let client = self.client.clone();
         self.startup
             .borrow_mut()
-            .push(Box::new(move || client.system().start(&task)));
-        Stream::new(self.client.clone(), connect, self.startup.clone())
+            .push(Box::new(move || start_task(&client, &task)));
+        Ok(stream)
     }
 }
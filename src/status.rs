@@ -0,0 +1,201 @@
+use kompact::prelude::*;
+
+use std::any::type_name;
+use std::any::TypeId;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+
+use crate::client::*;
+use crate::control::*;
+use crate::data::*;
+use crate::side::*;
+use crate::task::*;
+
+/// Termination status of a task.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskStatus {
+    Running,
+    Finished,
+    Failed(String),
+    Cancelled,
+}
+
+/// A message which cancels a task.
+#[derive(Debug, Clone)]
+pub struct Cancel;
+
+impl<R: DataReqs> From<Cancel> for TaskMessage<R> {
+    fn from(_: Cancel) -> Self {
+        TaskMessage::Cancel
+    }
+}
+
+struct Entry {
+    name: &'static str,
+    status: TaskStatus,
+    canceller: Recipient<Cancel>,
+}
+
+/// Tracks the status of all tasks in a pipeline.
+#[derive(Default)]
+pub struct Monitor {
+    entries: Mutex<Vec<Entry>>,
+    changed: Condvar,
+    /// Monitors of pipelines which were spawned by tasks of this pipeline.
+    children: Mutex<Vec<Arc<Monitor>>>,
+}
+
+/// Reports the status of a single task to its monitor.
+pub struct Reporter {
+    monitor: Arc<Monitor>,
+    index: usize,
+}
+
+impl Monitor {
+    pub(crate) fn register(
+        self: &Arc<Self>,
+        name: &'static str,
+        canceller: Recipient<Cancel>,
+    ) -> Reporter {
+        let mut entries = self.entries.lock().unwrap();
+        entries.push(Entry {
+            name,
+            status: TaskStatus::Running,
+            canceller,
+        });
+        Reporter {
+            monitor: self.clone(),
+            index: entries.len() - 1,
+        }
+    }
+
+    /// Returns the name and status of each task.
+    pub fn statuses(&self) -> Vec<(&'static str, TaskStatus)> {
+        let entries = self.entries.lock().unwrap();
+        entries.iter().map(|e| (e.name, e.status.clone())).collect()
+    }
+
+    /// Returns true if no task is running.
+    pub fn is_complete(&self) -> bool {
+        let entries = self.entries.lock().unwrap();
+        entries.iter().all(|e| e.status != TaskStatus::Running)
+    }
+
+    /// Block until no task is running.
+    pub fn await_completion(&self) -> Vec<(&'static str, TaskStatus)> {
+        let mut entries = self.entries.lock().unwrap();
+        while entries.iter().any(|e| e.status == TaskStatus::Running) {
+            entries = self.changed.wait(entries).unwrap();
+        }
+        entries.iter().map(|e| (e.name, e.status.clone())).collect()
+    }
+
+    /// Block until no task of this pipeline, or of any pipeline spawned by it, is running.
+    pub fn await_all(&self) {
+        self.await_completion();
+        // Children can only be spawned by running tasks, so the list can no longer grow
+        let children = self.children.lock().unwrap().clone();
+        for child in children {
+            child.await_all();
+        }
+    }
+
+    /// Track a pipeline which was spawned by a task of this pipeline.
+    pub(crate) fn adopt(&self, child: Arc<Monitor>) {
+        self.children.lock().unwrap().push(child);
+    }
+
+    /// Cancel all running tasks.
+    pub fn cancel(&self) {
+        let entries = self.entries.lock().unwrap();
+        for entry in entries.iter().filter(|e| e.status == TaskStatus::Running) {
+            entry.canceller.tell(Cancel);
+        }
+    }
+}
+
+impl Reporter {
+    /// Track a pipeline which was spawned by the task.
+    pub(crate) fn adopt(&self, child: Arc<Monitor>) {
+        self.monitor.adopt(child);
+    }
+
+    /// Set the status of the task unless it has already terminated.
+    pub(crate) fn report(&self, status: TaskStatus) {
+        let mut entries = self.monitor.entries.lock().unwrap();
+        let entry = &mut entries[self.index];
+        if entry.status == TaskStatus::Running {
+            entry.status = status;
+            self.monitor.changed.notify_all();
+        }
+    }
+}
+
+/// Create a task component of the pipeline of the client, together with its side outputs, and
+/// record it in the logical graph of the pipeline.
+pub(crate) fn create_task<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs>(
+    client: &Arc<Component<Client>>,
+    task: Task<S, I, O, R>,
+) -> Arc<Component<Task<S, I, O, R>>> {
+    let mut task = task;
+    let node = client.on_definition(|c| {
+        c.graph.add_node(
+            task.name,
+            type_name::<I>(),
+            type_name::<O>(),
+            task.chaining,
+            TypeId::of::<O>() == TypeId::of::<Never>(),
+        )
+    });
+    task.node = Some(node);
+    let side_fns = std::mem::take(&mut task.side_fns);
+    let task = client.system().create(move || task);
+    create_side_outputs(client, &task, side_fns);
+    task
+}
+
+/// Start a task, and track its status in the pipeline of the client from then on. Tasks of a
+/// pipeline which is never finalized are not tracked, so they do not keep the executor running.
+pub(crate) fn start_task<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs>(
+    client: &Arc<Component<Client>>,
+    task: &Arc<Component<Task<S, I, O, R>>>,
+) {
+    let monitor = client.on_definition(|c| c.monitor.clone());
+    start_side_outputs(client, task);
+    let canceller = task.actor_ref().recipient();
+    task.on_definition(|task| task.reporter = Some(monitor.register(task.name, canceller)));
+    client.system().start(task);
+}
+
+impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
+    /// Report the status of the task.
+    pub(crate) fn report(&self, status: TaskStatus) {
+        if let Some(reporter) = self.reporter.as_ref() {
+            reporter.report(status);
+        }
+    }
+
+    /// Run a handler. If it panics, the task fails and the rest of its pipeline is cancelled.
+    pub(crate) fn guard(&mut self, handler: impl FnOnce(&mut Self) -> Handled) -> Handled {
+        match panic::catch_unwind(AssertUnwindSafe(|| handler(self))) {
+            Ok(handled) => handled,
+            Err(cause) => {
+                let message = cause
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| cause.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "Unknown cause".to_string());
+                error!(self.log(), "Task {} failed: {}", self.name, message);
+                self.report(TaskStatus::Failed(message));
+                self.abort_side_outputs();
+                if let Some(reporter) = self.reporter.as_ref() {
+                    reporter.monitor.cancel();
+                }
+                Handled::DieNow
+            }
+        }
+    }
+}
@@ -1,29 +1,44 @@
 #![allow(clippy::type_complexity)]
 
 use kompact::prelude::*;
+use std::any::Any;
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::rc::Rc;
 use std::sync::Arc;
 
 use kompact::component::AbstractComponent;
 
+use crate::chain::*;
 use crate::client::*;
 use crate::control::*;
 use crate::data::*;
+use crate::graph::*;
 use crate::pipeline::*;
 use crate::port::*;
+use crate::side::*;
 use crate::task::*;
 
 pub type ErasedFn = Box<dyn FnOnce()>;
-pub type ConnectFn<T> = dyn Fn(&mut ProvidedPort<DataPort<T>>) + 'static;
+/// Connects the data and control ports of a consumer with the given id to a producer.
+pub type ConnectFn<T> =
+    dyn Fn(Uuid, &mut ProvidedPort<DataPort<T>>, &mut RequiredPort<CtrlPort>) + 'static;
 
 /// A stream which can be connected to `DataPorts`.
 #[derive(Clone)]
 pub struct Stream<T: DataReqs> {
     pub client: Arc<Component<Client>>,
-    pub connector: Arc<dyn Fn(&mut ProvidedPort<DataPort<T>>) + 'static>,
+    pub connector: Arc<ConnectFn<T>>,
     pub start_fns: Rc<RefCell<Vec<ErasedFn>>>,
+    /// Mailbox of the task which produces the stream.
+    pub producer: Option<Recipient<Command>>,
+    /// Node of the task which produces the stream in the logical graph.
+    pub node: Option<NodeId>,
+    /// Fuses the task which produces the stream with a consumer.
+    pub chain: Option<Arc<ChainFn<T>>>,
+    /// Streams of the side outputs of the producer.
+    pub sides: Rc<HashMap<&'static str, Rc<dyn Any>>>,
 }
 
 impl<I: DataReqs> Stream<I> {
@@ -31,11 +46,48 @@ impl<I: DataReqs> Stream<I> {
         client: Arc<Component<Client>>,
         connect: Arc<ConnectFn<I>>,
         starters: Rc<RefCell<Vec<ErasedFn>>>,
+        producer: Option<Recipient<Command>>,
+        node: Option<NodeId>,
     ) -> Self {
         Self {
             client,
             connector: connect,
             start_fns: starters,
+            producer,
+            node,
+            chain: None,
+            sides: Rc::new(HashMap::new()),
         }
     }
+
+    /// Create a stream of the output of a task.
+    pub(crate) fn from_task<S: DataReqs, X: DataReqs, R: DataReqs>(
+        client: Arc<Component<Client>>,
+        starters: Rc<RefCell<Vec<ErasedFn>>>,
+        task: &Arc<Component<Task<S, X, I, R>>>,
+    ) -> Self {
+        let producer = task.actor_ref().recipient();
+        let node = task.on_definition(|task| task.node);
+        let sides = side_streams(&client, &starters, task);
+        Self {
+            chain: Some(create_chain(task.clone())),
+            sides: Rc::new(sides),
+            ..Self::new(
+                client,
+                create_connector(task.clone()),
+                starters,
+                Some(producer),
+                node,
+            )
+        }
+    }
+
+    /// Returns a handle for reconfiguring the task which produces this stream.
+    pub(crate) fn reconfigurer(&self) -> Reconfigurer {
+        Reconfigurer(
+            self.producer
+                .clone()
+                .expect("Stream has no reconfigurable producer"),
+        )
+    }
 }
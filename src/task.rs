@@ -6,11 +6,21 @@ use arrayvec::ArrayVec;
 use kompact::component::AbstractComponent;
 use kompact::prelude::*;
 use time::*;
+use uuid::Uuid;
 
+use crate::asynchronous::*;
+use crate::batch::*;
+use crate::buffer::*;
+use crate::chain::*;
 use crate::control::*;
 use crate::data::*;
+use crate::graph::*;
+use crate::loops::*;
 use crate::pipeline::*;
 use crate::port::*;
+use crate::route::*;
+use crate::side::*;
+use crate::status::*;
 use crate::stream::*;
 use crate::timer::*;
 
@@ -25,6 +35,21 @@ pub enum Role {
     Consumer,
 }
 
+/// Logic of a task, which is called with each input event.
+pub type Logic<S, I, O, R> = Box<dyn FnMut(&mut Task<S, I, O, R>, I) + Send>;
+/// Trigger of a periodic processing-time timer.
+pub type Trigger<S, I, O, R> = Box<dyn FnMut(&mut Task<S, I, O, R>) + Send>;
+/// Hook which is called when the input stream of a task ends.
+pub type EndFn<S, I, O, R> = Box<dyn FnMut(&mut Task<S, I, O, R>) + Send>;
+/// Hook which is called when a loop notifies a task that a pointstamp is complete.
+pub type NotifyFn<S, I, O, R> = Box<dyn FnMut(&mut Task<S, I, O, R>, Vec<i32>) + Send>;
+/// Hook which is called when the watermark of a task advances.
+pub type WatermarkFn<S, I, O, R> = Box<dyn FnMut(&mut Task<S, I, O, R>, DateTime) + Send>;
+/// Hook which handles a command sent by a consumer.
+pub type CommandFn<S, I, O, R> = Box<dyn FnMut(&mut Task<S, I, O, R>, Command) + Send>;
+/// Hook which updates the state of a task with a configuration, if it accepts it.
+pub type ReconfigureFn<S> = Box<dyn FnMut(&mut S, &Command) -> bool + Send>;
+
 /// A general-purpose task component.
 #[derive(ComponentDefinition)]
 pub struct Task<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs = Never> {
@@ -35,28 +60,64 @@ pub struct Task<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs = Never> {
     pub data_oport: RequiredPort<DataPort<O>>,
     pub ctrl_iport: ProvidedPort<CtrlPort>,
     pub ctrl_oport: RequiredPort<CtrlPort>,
+    /// Input of a task which produces the stream of a side output.
+    pub side_iport: ProvidedPort<SidePort>,
+    /// Output of the events of all side outputs of the task.
+    pub side_oport: RequiredPort<SidePort>,
     pub lowest_observed_watermarks: Vec<DateTime>,
+    /// Latest watermark of each producer which has not ended.
+    pub watermarks: HashMap<Uuid, DateTime>,
     pub time: DateTime,
+    /// Time of the input event which is currently being processed, or of the timer which is
+    /// firing. Events which the task emits are stamped with it.
+    pub event_time: DateTime,
+    /// Loops which the event currently being processed is inside, outermost first.
+    pub scopes: Vec<Frame>,
     pub state: S,
-    pub logic: fn(&mut Self, I),
+    pub logic: Logic<S, I, O, R>,
+    /// Logic which is used instead of `logic` if the task is asynchronous.
+    pub async_logic: Option<AsyncLogic<S, I, O, R>>,
+    /// Number of spawned futures which have not yet completed.
+    pub spawned: usize,
+    pub on_command: Option<CommandFn<S, I, O, R>>,
+    pub on_end: Option<EndFn<S, I, O, R>>,
+    pub on_notify: Option<NotifyFn<S, I, O, R>>,
+    pub on_watermark: Option<WatermarkFn<S, I, O, R>>,
+    pub on_reconfigure: Option<ReconfigureFn<S>>,
+    /// Whether the lowest watermark among the producers is forwarded downstream.
+    pub forwarding: bool,
     pub ptimer: Option<ProcessingTimer<S, I, O, R>>,
     pub etimer: EventTimer<S, I, O, R>,
+    pub buffer: Buffer<O>,
+    pub batcher: Option<Batcher<O>>,
     pub role: Role,
+    pub reporter: Option<Reporter>,
+    /// Node of the task in the logical graph of its pipeline.
+    pub node: Option<NodeId>,
+    /// Outputs which only receive the events that are routed to them.
+    pub routes: Vec<Route<O>>,
+    /// Routes which the events that are currently emitted are sent to, as a bitmask.
+    pub routing: Option<u64>,
+    /// Side outputs which are created when the task is applied.
+    pub side_fns: Vec<(&'static str, SideFn)>,
+    pub side_outputs: HashMap<&'static str, SideOutput>,
+    /// Whether the task may be fused with its neighbours.
+    pub chaining: bool,
+    /// Consumer which output events are delivered to directly, if the task is fused with it.
+    pub chained: Option<ChainedFn<O>>,
 }
 
-#[derive(Clone)]
 pub struct ProcessingTimer<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> {
     duration: Duration,
     scheduled: Option<ScheduledTimer>,
-    trigger: fn(&mut Task<S, I, O, R>),
+    trigger: Trigger<S, I, O, R>,
 }
 
 impl<R: DataReqs, S: DataReqs, I: DataReqs, O: DataReqs> Actor for Task<S, I, O, R> {
-    type Message = Ask<(), R>;
+    type Message = TaskMessage<R>;
 
     fn receive_local(&mut self, msg: Self::Message) -> Handled {
-        self.promise = Some(msg);
-        Handled::Ok
+        self.guard(|task| task.handle_message(msg))
     }
 
     fn receive_network(&mut self, msg: NetMessage) -> Handled {
@@ -65,7 +126,38 @@ impl<R: DataReqs, S: DataReqs, I: DataReqs, O: DataReqs> Actor for Task<S, I, O,
 }
 
 impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
-    pub(crate) fn new(name: &'static str, state: S, logic: fn(&mut Self, I)) -> Self {
+    fn handle_message(&mut self, msg: TaskMessage<R>) -> Handled {
+        match msg {
+            TaskMessage::Promise(promise) => self.promise = Some(promise),
+            TaskMessage::Reconfigure(config) => {
+                if !self.reconfigure(&config) {
+                    warn!(self.log(), "Ignored unexpected configuration {:?}", config);
+                }
+            }
+            TaskMessage::Die => self.stop(),
+            TaskMessage::Notify(pointstamp) => {
+                // Take the hook out of the task while it is borrowed by the hook
+                if let Some(mut callback) = self.on_notify.take() {
+                    callback(self, pointstamp);
+                    self.on_notify = Some(callback);
+                }
+            }
+            TaskMessage::Cancel => {
+                self.abort_side_outputs();
+                self.report(TaskStatus::Cancelled);
+                return Handled::DieNow;
+            }
+        }
+        Handled::Ok
+    }
+}
+
+impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
+    pub(crate) fn new(
+        name: &'static str,
+        state: S,
+        logic: impl FnMut(&mut Self, I) + Send + 'static,
+    ) -> Self {
         Self {
             ctx: ComponentContext::uninitialised(),
             name,
@@ -74,13 +166,36 @@ impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
             data_oport: RequiredPort::uninitialised(),
             ctrl_iport: ProvidedPort::uninitialised(),
             ctrl_oport: RequiredPort::uninitialised(),
+            side_iport: ProvidedPort::uninitialised(),
+            side_oport: RequiredPort::uninitialised(),
             state,
-            logic,
+            logic: Box::new(logic),
+            async_logic: None,
+            spawned: 0,
+            on_command: None,
+            on_end: None,
+            on_notify: None,
+            on_watermark: None,
+            on_reconfigure: None,
+            forwarding: true,
             ptimer: None,
             role: Role::ProducerConsumer,
             lowest_observed_watermarks: vec![],
+            watermarks: HashMap::new(),
             time: DateTime::unix_epoch(),
+            event_time: DateTime::unix_epoch(),
+            scopes: Vec::new(),
             etimer: EventTimer::default(),
+            buffer: Buffer::new(DEFAULT_CAPACITY),
+            batcher: None,
+            reporter: None,
+            node: None,
+            routes: Vec::new(),
+            routing: None,
+            side_fns: Vec::new(),
+            side_outputs: HashMap::new(),
+            chaining: true,
+            chained: None,
         }
     }
 
@@ -92,43 +207,164 @@ impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
         name: &'static str,
         state: S,
         duration: Duration,
-        trigger: fn(&mut Self),
+        trigger: impl FnMut(&mut Self) + Send + 'static,
     ) -> Self {
         Self {
             ptimer: Some(ProcessingTimer {
                 duration,
-                trigger,
+                trigger: Box::new(trigger),
                 scheduled: None,
             }),
             ..Self::new(name, state, |_, _| {})
         }
     }
 
-    pub(crate) fn min_watermark(&self) -> DateTime {
-        *self.lowest_observed_watermarks.iter().min().unwrap()
+    /// Call the logic of the task with an input event and its time.
+    pub(crate) fn process(&mut self, time: DateTime, data: I) {
+        self.event_time = time;
+        // Take the logic out of the task while it is borrowed by the logic
+        let mut logic = std::mem::replace(&mut self.logic, Box::new(|_, _| {}));
+        logic(self, data);
+        self.logic = logic;
+        self.event_time = self.time;
+    }
+
+    /// Returns the lowest watermark among the producers, if all of them have sent one.
+    pub(crate) fn min_watermark(&self) -> Option<DateTime> {
+        let live = self.buffer.producers - self.buffer.ended;
+        if self.watermarks.len() >= live {
+            self.watermarks.values().min().copied()
+        } else {
+            None
+        }
+    }
+
+    /// Send a watermark downstream.
+    pub(crate) fn send_watermark(&mut self, time: DateTime) {
+        let id = self.ctx.id();
+        self.send(DataEvent::Watermark(id, time));
+    }
+
+    /// Advance event time to the lowest watermark among the producers, and forward it unless the
+    /// task holds back watermarks.
+    fn advance_watermark(&mut self) {
+        if let Some(time) = self.min_watermark() {
+            if time > self.time {
+                let diff = time - self.time;
+                self.advance(diff.to_std().unwrap());
+                if let Some(mut callback) = self.on_watermark.take() {
+                    callback(self, time);
+                    self.on_watermark = Some(callback);
+                }
+                if self.forwarding {
+                    self.send_watermark(time);
+                }
+            }
+        }
     }
 
+    /// Emit an event with the time of the input event or timer which it is the output of.
     pub(crate) fn emit(&mut self, data: O) {
-        self.data_oport.trigger(DataEvent::Item(self.time, data));
+        let time = self.event_time;
+        self.emit_at(time, data);
+    }
+
+    /// Emit an event with a time other than the current event time of the task, such as the
+    /// time of an earlier input event whose output was deferred.
+    pub(crate) fn emit_at(&mut self, time: DateTime, data: O) {
+        if self.scopes.is_empty() {
+            self.send(DataEvent::Item(time, data));
+        } else {
+            // Every consumer retires the event once it has processed it
+            produce(&self.scopes, self.recipients());
+            self.send(DataEvent::Scoped(self.scopes.clone(), time, data));
+        }
+    }
+
+    /// Set a callback which is called when the input stream ends, before the end is forwarded.
+    /// The callback may call `exit` to return a value.
+    pub(crate) fn on_end(self, callback: impl FnMut(&mut Self) + Send + 'static) -> Self {
+        Self {
+            on_end: Some(Box::new(callback)),
+            ..self
+        }
+    }
+
+    /// Set a callback which is called when a loop notifies the task that a pointstamp is
+    /// complete.
+    pub(crate) fn on_notify(
+        self,
+        callback: impl FnMut(&mut Self, Vec<i32>) + Send + 'static,
+    ) -> Self {
+        Self {
+            on_notify: Some(Box::new(callback)),
+            ..self
+        }
     }
 
-    /// Die with a return value
+    /// Set a callback which is called when the lowest watermark among the producers advances,
+    /// after the task's event-time timers up to the watermark have fired and before the
+    /// watermark is forwarded.
+    pub(crate) fn on_watermark(
+        self,
+        callback: impl FnMut(&mut Self, DateTime) + Send + 'static,
+    ) -> Self {
+        Self {
+            on_watermark: Some(Box::new(callback)),
+            ..self
+        }
+    }
+
+    /// Stop forwarding watermarks, so that the task can send them itself with `send_watermark`.
+    pub(crate) fn hold_watermarks(self) -> Self {
+        Self {
+            forwarding: false,
+            ..self
+        }
+    }
+
+    /// Returns a future which is completed with the value the task passes to `exit`.
+    /// The future fails if the task terminates without calling `exit`.
+    pub(crate) fn terminating(mut self) -> (Self, KFuture<R>) {
+        let (promise, future) = promise();
+        self.promise = Some(Ask::new(promise, ()));
+        (self, future)
+    }
+
+    /// Die with a return value once the output buffer is drained
     pub(crate) fn exit(&mut self, rval: R) {
         if let Some(promise) = self.promise.take() {
             promise.reply(rval);
         };
-        self.data_oport.trigger(DataEvent::End);
-        self.ctx.suicide();
+        self.send(DataEvent::End(self.ctx.id()));
+        self.end_side_outputs();
     }
 
     pub(crate) fn oneshot_trigger(&mut self, timeout: ScheduledTimer) -> Handled {
         match self.ptimer.as_mut().unwrap().scheduled.as_ref() {
             Some(scheduled_oneshot) if *scheduled_oneshot == timeout => {
-                let oneshot = self.ptimer.as_ref().unwrap();
-                let duration = oneshot.duration;
-                (oneshot.trigger)(self);
+                // Take the trigger out of the task while it is borrowed by the trigger
+                let ptimer = self.ptimer.as_mut().unwrap();
+                let mut trigger = std::mem::replace(&mut ptimer.trigger, Box::new(|_| {}));
+                let handled = self.guard(|task| {
+                    trigger(task);
+                    Handled::Ok
+                });
+                if let Some(ptimer) = self.ptimer.as_mut() {
+                    ptimer.trigger = trigger;
+                }
+                if let Handled::DieNow = handled {
+                    return Handled::DieNow;
+                }
+                // The trigger may have changed the period
+                let duration = self.ptimer.as_ref().unwrap().duration;
+                // Pause the timer while the output buffer is full, it is resumed when drained
                 self.ptimer.as_mut().unwrap().scheduled =
-                    Some(self.schedule_once(duration, Self::oneshot_trigger));
+                    if self.is_congested() || self.buffer.paused || self.buffer.terminating {
+                        None
+                    } else {
+                        Some(self.schedule_once(duration, Self::oneshot_trigger))
+                    };
                 Handled::Ok
             }
             Some(_) => Handled::Ok,
@@ -138,68 +374,131 @@ impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
             }
         }
     }
+
+    /// Set the duration until the next time the periodic timer is triggered.
+    pub(crate) fn set_period(&mut self, duration: Duration) {
+        if let Some(ptimer) = self.ptimer.as_mut() {
+            ptimer.duration = duration;
+        }
+    }
+
+    /// Resume a periodic timer which was paused by backpressure.
+    pub(crate) fn resume_timer(&mut self) {
+        if let Some(ptimer) = self.ptimer.as_ref() {
+            if ptimer.scheduled.is_none() && !self.buffer.paused && !self.buffer.terminating {
+                let duration = ptimer.duration;
+                self.ptimer.as_mut().unwrap().scheduled =
+                    Some(self.schedule_once(duration, Self::oneshot_trigger));
+            }
+        }
+    }
 }
 
 impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> FnOnce<(Stream<I>,)> for Task<S, I, O, R> {
     type Output = Stream<O>;
 
     extern "rust-call" fn call_once(self, (stream,): (Stream<I>,)) -> Self::Output {
-        // Step 1. Initialise the task
-        let task = stream.client.system().create(|| self);
+        // Step 1. Initialise the task and its side outputs
+        let task = create_task(&stream.client, self);
         // Step 2. Connect the input streams to each of the task's input ports
-        task.on_definition(|consumer| {
-            (stream.connector)(&mut consumer.data_iport);
-        });
+        task.on_definition(|consumer| consumer.connect_input(&stream));
+        link(&stream, &task);
         // Step 3. Create a stream for each of the task's output ports
-        let producer = task.clone();
-        let connector: Arc<ConnectFn<_>> = Arc::new(move |iport| {
-            producer.on_definition(|producer| {
-                iport.connect(producer.data_oport.share());
-                producer.data_oport.connect(iport.share());
-            });
-        });
+        let output = Stream::from_task(stream.client.clone(), stream.start_fns.clone(), &task);
         // Step 4. Create a closure for starting up the task
-        let start_fns = stream.start_fns.clone();
         let client = stream.client.clone();
         stream
             .start_fns
             .borrow_mut()
-            .push(Box::new(move || client.system().start(&task)));
-        let client = stream.client.clone();
-        (Stream::new(client, connector, start_fns))
+            .push(Box::new(move || start_task(&client, &task)));
+        output
+    }
+}
+
+impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
+    /// Connect the input of the task to a stream.
+    pub(crate) fn connect_input(&mut self, stream: &Stream<I>) {
+        let id = self.ctx.id();
+        (stream.connector)(id, &mut self.data_iport, &mut self.ctrl_oport);
+        self.buffer.producers += 1;
+        if let (Some(from), Some(to)) = (stream.node, self.node) {
+            stream.client.on_definition(|c| c.graph.add_edge(from, to));
+        }
     }
 }
 
 pub(crate) fn create_connector<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs>(
     producer: Arc<Component<Task<S, I, O, R>>>,
-) -> Arc<dyn Fn(&mut ProvidedPort<DataPort<O>>) + 'static> {
-    Arc::new(move |mut iport| {
+) -> Arc<ConnectFn<O>> {
+    Arc::new(move |_, iport, oport| {
         producer.on_definition(|producer| {
             iport.connect(producer.data_oport.share());
             producer.data_oport.connect(iport.share());
+            oport.connect(producer.ctrl_iport.share());
+            producer.ctrl_iport.connect(oport.share());
+            producer.buffer.consumers += 1;
         })
     })
 }
 
 impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Provide<DataPort<I>> for Task<S, I, O, R> {
     fn handle(&mut self, event: DataEvent<I>) -> Handled {
+        self.guard(|task| task.handle_data(event))
+    }
+}
+
+impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
+    pub(crate) fn handle_data(&mut self, event: DataEvent<I>) -> Handled {
         match event {
-            DataEvent::Watermark(time) => {
-                if time > self.time {
-                    let diff = time - self.time;
-                    self.advance(diff.to_std().unwrap());
-                }
+            DataEvent::Item(..) | DataEvent::Scoped(..) | DataEvent::Batch(..)
+                if self.async_logic.is_some() =>
+            {
+                self.handle_async(event)
+            }
+            DataEvent::Watermark(id, time) => {
+                self.watermarks.insert(id, time);
+                self.advance_watermark();
+                self.ack();
                 Handled::Ok
             }
             DataEvent::Item(time, data) => {
                 if time >= self.time {
-                    (self.logic)(self, data);
+                    self.process(time, data);
                 }
+                self.ack();
                 Handled::Ok
             }
-            DataEvent::End => {
-                self.data_oport.trigger(DataEvent::End);
-                Handled::DieNow
+            DataEvent::Scoped(scopes, time, data) => {
+                self.scopes = scopes.clone();
+                if time >= self.time {
+                    self.process(time, data);
+                }
+                self.scopes.clear();
+                retire(&scopes);
+                self.ack();
+                Handled::Ok
+            }
+            DataEvent::Batch(batch) => {
+                // Each item of a batch was charged one credit
+                for (time, data) in batch {
+                    if time >= self.time {
+                        self.process(time, data);
+                    }
+                    self.ack();
+                }
+                Handled::Ok
+            }
+            DataEvent::Routed(..) => unreachable!("Routed events are unwrapped when sent"),
+            DataEvent::End(id) => {
+                // An ended producer no longer holds back the watermark
+                self.buffer.ended += 1;
+                self.watermarks.remove(&id);
+                self.advance_watermark();
+                // End the output once all producers have ended and all spawned futures completed
+                if self.buffer.ended >= self.buffer.producers && self.spawned == 0 {
+                    self.finish();
+                }
+                Handled::Ok
             }
         }
     }
@@ -207,13 +506,66 @@ impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Provide<DataPort<I>> fo
 
 impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Require<DataPort<O>> for Task<S, I, O, R> {
     fn handle(&mut self, event: DataReply) -> Handled {
+        match event {
+            DataReply::Pull(id, amount) => self.credit(id, amount),
+        }
         Handled::Ok
     }
 }
 
 impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Provide<CtrlPort> for Task<S, I, O, R> {
     fn handle(&mut self, event: CtrlEvent) -> Handled {
-        todo!()
+        self.guard(|task| task.handle_ctrl(event))
+    }
+}
+
+impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
+    fn handle_ctrl(&mut self, event: CtrlEvent) -> Handled {
+        let id = self.ctx.id();
+        match &event {
+            CtrlEvent::Pause(consumer) => {
+                // A producer which is shared with other consumers keeps sending to them, and is
+                // only paused once all of them have paused it
+                self.buffer.pausing.insert(*consumer);
+                if !self.buffer.paused && self.buffer.pausing.len() >= self.buffer.consumers {
+                    self.pause();
+                    self.send_upstream(CtrlEvent::Pause(id));
+                }
+                return Handled::Ok;
+            }
+            CtrlEvent::Resume(consumer) => {
+                self.buffer.pausing.remove(consumer);
+                if self.buffer.paused {
+                    self.resume();
+                    self.send_upstream(CtrlEvent::Resume(id));
+                }
+                return Handled::Ok;
+            }
+            CtrlEvent::Flush(_) => {
+                // Flushing a shared producer would cut short the batches of its other consumers
+                if self.buffer.consumers <= 1 {
+                    self.flush_batch();
+                    self.flush();
+                    self.send_upstream(CtrlEvent::Flush(id));
+                }
+                return Handled::Ok;
+            }
+            CtrlEvent::Reconfigure(config) => {
+                if self.reconfigure(config) {
+                    return Handled::Ok;
+                }
+            }
+            CtrlEvent::Command(command) => {
+                if let Some(mut handler) = self.on_command.take() {
+                    handler(self, command.clone());
+                    self.on_command = Some(handler);
+                    return Handled::Ok;
+                }
+            }
+        }
+        // Propagate the event to the producers of this task
+        self.send_upstream(event);
+        Handled::Ok
     }
 }
 
@@ -225,6 +577,9 @@ impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Require<CtrlPort> for T
 
 impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> ComponentLifecycle for Task<S, I, O, R> {
     fn on_start(&mut self) -> Handled {
+        // Grant initial credit to the producers
+        self.buffer.consumed = self.buffer.capacity;
+        self.grant();
         if let Some(oneshot) = self.ptimer.as_mut() {
             let duration = oneshot.duration;
             self.ptimer.as_mut().unwrap().scheduled =
@@ -234,13 +589,20 @@ impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> ComponentLifecycle for
     }
 
     fn on_stop(&mut self) -> Handled {
-        if let Some(scheduled_oneshot) = self.ptimer.as_mut().unwrap().scheduled.take() {
+        if let Some(scheduled_oneshot) = self.ptimer.as_mut().and_then(|p| p.scheduled.take()) {
             self.cancel_timer(scheduled_oneshot);
         }
         Handled::Ok
     }
 
     fn on_kill(&mut self) -> Handled {
+        // A task which is killed before ending its output stream did not finish
+        if self.buffer.terminating {
+            self.report(TaskStatus::Finished);
+        } else {
+            self.abort_side_outputs();
+            self.report(TaskStatus::Cancelled);
+        }
         Handled::Ok
     }
 }
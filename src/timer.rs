@@ -5,6 +5,7 @@ use hierarchical_hash_wheel_timer::wheels::Skip;
 use hierarchical_hash_wheel_timer::wheels::TimerEntryWithDelay;
 use hierarchical_hash_wheel_timer::TimerError;
 use hierarchical_hash_wheel_timer::UuidOnlyTimerEntry as Entry;
+use kompact::prelude::*;
 use time::PrimitiveDateTime as DateTime;
 use uuid::Uuid;
 
@@ -12,14 +13,16 @@ use crate::data::DataReqs;
 use crate::task::Task;
 
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::time::Duration;
 
 pub struct EventTimer<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> {
     pub wheel: QuadWheelWithOverflow<Entry>,
-    pub data: HashMap<Uuid, fn(&mut Task<S, I, O, R>)>,
+    pub data: HashMap<Uuid, Callback<S, I, O, R>>,
 }
 
-type Callback<S, I, O, R> = fn(&mut Task<S, I, O, R>);
+/// Callback of an event-time timer.
+pub type Callback<S, I, O, R> = Box<dyn FnOnce(&mut Task<S, I, O, R>) + Send>;
 
 impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Default for EventTimer<S, I, O, R> {
     fn default() -> Self {
@@ -32,12 +35,48 @@ impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Default for EventTimer<
 
 impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
     /// Execute callback after duration
-    pub fn after(&mut self, dur: Duration, cb: Callback<S, I, O, R>) {
+    pub fn after(&mut self, dur: Duration, cb: impl FnOnce(&mut Self) + Send + 'static) {
         let entry = Entry::with_random_id(dur);
-        self.etimer.data.insert(entry.id, cb);
+        self.etimer.data.insert(entry.id, Box::new(cb));
         self.etimer.wheel.insert(entry).unwrap();
     }
 
+    /// Fire all remaining timers in order, as if the final watermark had arrived.
+    /// Timers which the callbacks set while draining also fire if they expire before the last of
+    /// the remaining timers. Later ones are discarded, since a callback which re-arms itself would
+    /// otherwise keep the task from ever ending.
+    pub(crate) fn drain_timers(&mut self) {
+        let mut pending: HashSet<Uuid> = self.etimer.data.keys().copied().collect();
+        while !pending.is_empty() {
+            match self.etimer.wheel.can_skip() {
+                Skip::Empty => break,
+                Skip::None => {
+                    self.time += Duration::from_millis(1);
+                    self.event_time = self.time;
+                    for e in self.etimer.wheel.tick() {
+                        pending.remove(&e.id);
+                        (self.etimer.data.remove(&e.id).unwrap())(self);
+                    }
+                }
+                Skip::Millis(skip) => {
+                    self.etimer.wheel.skip(skip);
+                    self.time += Duration::from_millis(skip as u64);
+                }
+            }
+        }
+        self.event_time = self.time;
+        if !self.etimer.data.is_empty() {
+            warn!(
+                self.log(),
+                "Discarded {} timers which were set while draining",
+                self.etimer.data.len()
+            );
+            self.etimer = EventTimer::default();
+        }
+    }
+
+    /// Advance the time of the task, firing the timers which expire on the way. Events which
+    /// the timers emit are stamped with the time at which they fire.
     /// TODO: Handle overflow. Currently assumes Duration <= u32::MAX.
     pub(crate) fn advance(&mut self, mut remaining: Duration) {
         while remaining.as_millis() > 0 {
@@ -45,11 +84,13 @@ impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
                 // No timers are scheduled
                 Skip::Empty => {
                     self.time += remaining;
+                    break;
                 }
                 // Timers are scheduled at the next millisecond
                 Skip::None => {
                     self.time += Duration::from_millis(1);
                     remaining -= Duration::from_millis(1);
+                    self.event_time = self.time;
                     for e in self.etimer.wheel.tick() {
                         (self.etimer.data.remove(&e.id).unwrap())(self);
                     }
@@ -59,6 +100,7 @@ impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
                     if skip as u128 >= remaining.as_millis() {
                         // No more entries to expire
                         self.etimer.wheel.skip(remaining.as_millis() as u32);
+                        self.time += remaining;
                         break;
                     } else {
                         // Skip until next entry
@@ -69,5 +111,6 @@ impl<S: DataReqs, I: DataReqs, O: DataReqs, R: DataReqs> Task<S, I, O, R> {
                 }
             }
         }
+        self.event_time = self.time;
     }
 }
@@ -0,0 +1,171 @@
+use kompact::prelude::*;
+
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Duration;
+
+use crate::data::*;
+use crate::keyed::*;
+use crate::stream::*;
+use crate::task::*;
+use crate::window::*;
+
+/// How the most frequent keys of a window are found.
+#[derive(Debug, Clone, Copy)]
+pub enum TopK {
+    /// Count every key of the window exactly.
+    Exact,
+    /// Estimate the counts of the heavy hitters of the window with the space-saving algorithm,
+    /// using a bounded number of counters.
+    HeavyHitters { counters: usize },
+}
+
+/// Counts of the keys of a window.
+#[derive(Debug, Clone)]
+pub enum Counts<K: Hash + Eq> {
+    Exact(HashMap<K, u64>),
+    HeavyHitters(SpaceSaving<K>),
+}
+
+/// Approximate counts of the most frequent keys, using a bounded number of counters. When all
+/// counters are taken, the key with the lowest count is replaced, and its count is inherited as
+/// the maximum overestimation of the new key.
+#[derive(Debug, Clone)]
+pub struct SpaceSaving<K: Hash + Eq> {
+    capacity: usize,
+    /// Estimated count and maximum overestimation of each tracked key.
+    counters: HashMap<K, (u64, u64)>,
+    /// Tracked keys ordered by their estimated count, so the lowest is found without a scan.
+    by_count: BTreeSet<(u64, K)>,
+}
+
+impl<K: Hash + Ord + Clone> SpaceSaving<K> {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "Space-saving needs at least one counter");
+        Self {
+            capacity,
+            counters: HashMap::new(),
+            by_count: BTreeSet::new(),
+        }
+    }
+
+    pub fn insert(&mut self, key: K) {
+        if let Some((count, _)) = self.counters.get_mut(&key) {
+            self.by_count.remove(&(*count, key.clone()));
+            *count += 1;
+            self.by_count.insert((*count, key));
+        } else if self.counters.len() < self.capacity {
+            self.counters.insert(key.clone(), (1, 0));
+            self.by_count.insert((1, key));
+        } else {
+            // Ties between the lowest counts are broken by key
+            let (min_count, min_key) = self.by_count.iter().next().cloned().unwrap();
+            self.by_count.remove(&(min_count, min_key.clone()));
+            self.counters.remove(&min_key);
+            self.counters
+                .insert(key.clone(), (min_count + 1, min_count));
+            self.by_count.insert((min_count + 1, key));
+        }
+    }
+
+    /// Returns the estimated count and maximum overestimation of each tracked key.
+    pub fn counters(&self) -> impl Iterator<Item = (&K, &(u64, u64))> {
+        self.counters.iter()
+    }
+}
+
+impl<K: Hash + Ord + Clone> Counts<K> {
+    fn new(mode: TopK) -> Self {
+        match mode {
+            TopK::Exact => Counts::Exact(HashMap::new()),
+            TopK::HeavyHitters { counters } => Counts::HeavyHitters(SpaceSaving::new(counters)),
+        }
+    }
+
+    fn insert(&mut self, key: K) {
+        match self {
+            Counts::Exact(counts) => *counts.entry(key).or_insert(0) += 1,
+            Counts::HeavyHitters(counts) => counts.insert(key),
+        }
+    }
+
+    /// Returns the `k` keys with the highest counts, highest first, together with the maximum
+    /// overestimation of each count. Keys with equal counts are ordered by key.
+    fn top(&self, k: usize) -> Vec<(K, u64, u64)> {
+        let counts = match self {
+            Counts::Exact(counts) => counts
+                .iter()
+                .map(|(key, count)| (key, *count, 0))
+                .collect::<Vec<_>>(),
+            Counts::HeavyHitters(counts) => counts
+                .counters()
+                .map(|(key, (count, error))| (key, *count, *error))
+                .collect(),
+        };
+        // Keep the k highest counts in a bounded heap, whose top is the lowest count and, among
+        // equal counts, the highest key
+        let mut heap = BinaryHeap::new();
+        for (key, count, error) in counts {
+            heap.push((Reverse(count), key, error));
+            if heap.len() > k {
+                heap.pop();
+            }
+        }
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|(Reverse(count), key, error)| (key.clone(), count, error))
+            .collect()
+    }
+}
+
+impl<K: DataReqs + Hash + Ord, T: DataReqs> KeyedStream<K, T> {
+    /// Find the `k` most frequent keys of each tumbling event-time window of `size`, and emit
+    /// them with their counts, highest first and ties ordered by key, when the window's timer
+    /// fires. Each count comes with its maximum overestimation, which is zero in exact mode.
+    pub fn top_k(self, size: Duration, k: usize, mode: TopK) -> Stream<Vec<(K, u64, u64)>> {
+        let key = self.key;
+        self.stream.apply(Task::new(
+            "TopK",
+            Window::new(size, Counts::new(mode)),
+            move |task: &mut Task<Window<Counts<K>>, T, Vec<(K, u64, u64)>, Never>, event: T| {
+                let key = key(&event);
+                assign(
+                    task,
+                    |counts| counts.insert(key),
+                    move |task, counts| task.emit(counts.top(k)),
+                );
+            },
+        ))
+    }
+}
+
+impl<T: DataReqs + Ord> Stream<T> {
+    /// Find the `k` largest events of each tumbling event-time window of `size`, and emit them,
+    /// largest first, when the window's timer fires.
+    pub fn largest(self, size: Duration, k: usize) -> Stream<Vec<T>> {
+        self.apply(Task::new(
+            "Largest",
+            Window::new(size, BinaryHeap::new()),
+            move |task: &mut Task<Window<BinaryHeap<Reverse<T>>>, T, Vec<T>, Never>, event: T| {
+                // Keep the k largest events in a bounded min-heap
+                let update = |heap: &mut BinaryHeap<Reverse<T>>| {
+                    heap.push(Reverse(event));
+                    if heap.len() > k {
+                        heap.pop();
+                    }
+                };
+                assign(task, update, |task, heap| {
+                    let largest = heap
+                        .into_sorted_vec()
+                        .into_iter()
+                        .map(|Reverse(event)| event)
+                        .collect();
+                    task.emit(largest);
+                });
+            },
+        ))
+    }
+}
@@ -1,13 +1,16 @@
 use crate::prelude::*;
+use crate::status::*;
 use crate::task::*;
 
 impl<I: DataReqs> Stream<I> {
     /// Transform a stream into a new stream.
+    /// Also returns a future which is completed with the value the task passes to `exit`.
     pub(crate) fn apply_terminating<S: DataReqs, O: DataReqs, R: DataReqs>(
         self,
         task: Task<S, I, O, R>,
-    ) -> Stream<O> {
-        task(self)
+    ) -> (Stream<O>, KFuture<R>) {
+        let (task, result) = task.terminating();
+        (task(self), result)
     }
     /// Transform a stream into a new stream.
     pub(crate) fn apply<S: DataReqs, O: DataReqs>(self, task: Task<S, I, O, Never>) -> Stream<O> {
@@ -20,39 +23,75 @@ impl<I: DataReqs> Stream<I> {
         other: Stream<X>,
         task: Task<S, Either<I, X>, O, Never>,
     ) -> Stream<O> {
-        let mergel: Arc<Component<Task<_, _, _, _>>> = self.client.system().create(|| {
+        let mergel = create_task(
+            &self.client,
             Task::new(
                 "Merge Left",
                 (),
                 |task: &mut Task<(), I, Either<I, X>, Never>, event| task.emit(Either::L(event)),
-            )
-        });
-        let merger: Arc<Component<Task<_, _, _, _>>> = self.client.system().create(|| {
+            ),
+        );
+        let merger = create_task(
+            &self.client,
             Task::new(
                 "Merge Right",
                 (),
                 |task: &mut Task<(), X, Either<I, X>, Never>, event| task.emit(Either::R(event)),
-            )
+            ),
+        );
+        let task = create_task(&self.client, task);
+        task.on_definition(|c| {
+            c.connect_input(&Stream::from_task(
+                self.client.clone(),
+                self.start_fns.clone(),
+                &mergel,
+            ));
+            c.connect_input(&Stream::from_task(
+                self.client.clone(),
+                self.start_fns.clone(),
+                &merger,
+            ));
         });
-        let task = self.client.system().create(|| task);
-        biconnect_components::<DataPort<_>, _, _>(&task, &mergel);
-        biconnect_components::<DataPort<_>, _, _>(&task, &merger);
-        mergel.on_definition(|c| (self.connector)(&mut c.data_iport));
-        merger.on_definition(|c| (other.connector)(&mut c.data_iport));
-        let connect = create_connector(task.clone());
+        mergel.on_definition(|c| c.connect_input(&self));
+        merger.on_definition(|c| c.connect_input(&other));
+        let stream = Stream::from_task(self.client.clone(), self.start_fns.clone(), &task);
+        let client = self.client.clone();
+        self.start_fns
+            .borrow_mut()
+            .push(Box::new(move || start_task(&client, &mergel)));
         let client = self.client.clone();
         self.start_fns
             .borrow_mut()
-            .push(Box::new(move || client.system().start(&mergel)));
+            .push(Box::new(move || start_task(&client, &merger)));
         let client = self.client.clone();
         self.start_fns
             .borrow_mut()
-            .push(Box::new(move || client.system().start(&merger)));
+            .push(Box::new(move || start_task(&client, &task)));
+        stream
+    }
+
+    /// Combine any number of streams into one, by connecting all of them to the input of a
+    /// single task. The output ends once all input streams have ended, and its watermark is the
+    /// lowest watermark among the input streams.
+    pub(crate) fn union(self, others: Vec<Stream<I>>) -> Stream<I> {
+        let task = create_task(
+            &self.client,
+            Task::new("Union", (), |task: &mut Task<(), I, I, Never>, event| {
+                task.emit(event)
+            }),
+        );
+        task.on_definition(|c| {
+            c.connect_input(&self);
+            for other in &others {
+                c.connect_input(other);
+            }
+        });
+        let stream = Stream::from_task(self.client.clone(), self.start_fns.clone(), &task);
         let client = self.client.clone();
         self.start_fns
             .borrow_mut()
-            .push(Box::new(move || client.system().start(&task)));
-        Stream::new(self.client, connect, self.start_fns)
+            .push(Box::new(move || start_task(&client, &task)));
+        stream
     }
 
     /// Transform one stream into two.
@@ -0,0 +1,62 @@
+use kompact::prelude::*;
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::data::*;
+use crate::task::*;
+
+/// State of a task which aggregates tumbling event-time windows.
+#[derive(Debug, Clone)]
+pub struct Window<A> {
+    size: Duration,
+    /// Contents of each window which has not yet fired, by the index of the window.
+    pub windows: BTreeMap<u128, A>,
+    /// Contents of an empty window.
+    empty: A,
+}
+
+impl<A: DataReqs> Window<A> {
+    pub(crate) fn new(size: Duration, empty: A) -> Self {
+        assert!(
+            size.as_millis() > 0,
+            "Window size must be at least a millisecond"
+        );
+        Self {
+            size,
+            windows: BTreeMap::new(),
+            empty,
+        }
+    }
+}
+
+/// Returns the index of the tumbling window of `size` which contains `time`.
+fn window_index(time: DateTime, size: Duration) -> u128 {
+    let elapsed = (time - DateTime::unix_epoch()).to_std().unwrap_or_default();
+    elapsed.as_millis() / size.as_millis()
+}
+
+/// Add the current input event to the window which contains its event time, by calling `update`
+/// with the contents of that window. When a window is opened, a timer is set for its end, and
+/// `fire` is called with its contents once the watermark has passed it.
+pub(crate) fn assign<A: DataReqs, I: DataReqs, O: DataReqs>(
+    task: &mut Task<Window<A>, I, O, Never>,
+    update: impl FnOnce(&mut A),
+    fire: impl FnOnce(&mut Task<Window<A>, I, O, Never>, A) + Send + 'static,
+) {
+    let size = task.state.size;
+    let index = window_index(task.event_time, size);
+    if !task.state.windows.contains_key(&index) {
+        let empty = task.state.empty.clone();
+        task.state.windows.insert(index, empty);
+        // Events older than the watermark are discarded, so the window ends after it
+        let end =
+            DateTime::unix_epoch() + Duration::from_millis(((index + 1) * size.as_millis()) as u64);
+        let remaining = (end - task.time).to_std().unwrap_or_default();
+        task.after(remaining, move |task| {
+            let acc = task.state.windows.remove(&index).unwrap();
+            fire(task, acc);
+        });
+    }
+    update(task.state.windows.get_mut(&index).unwrap());
+}
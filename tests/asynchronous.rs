@@ -0,0 +1,69 @@
+#![allow(unused)]
+
+use arctime::asynchronous::*;
+use arctime::data::*;
+use arctime::executor::*;
+use arctime::pipeline::*;
+use arctime::source::*;
+use arctime::task::*;
+
+use kompact::prelude::*;
+use std::sync::mpsc;
+use std::time::Duration;
+
+async fn lookup(key: i32) -> i32 {
+    key * 10
+}
+
+#[test]
+fn ordered() {
+    let executor = Executor::new();
+    let pipeline = executor.pipeline();
+    let (tx, rx) = mpsc::channel();
+
+    pipeline
+        .source(timestamped(0..10), Duration::new(0, 5_000_000))
+        .unwrap()
+        .apply(Task::new_async("Lookup", (), |task, key| async move {
+            Some(lookup(key).await)
+        }))
+        .sink(Task::new("Collect", (), move |task, event| {
+            tx.send(event).unwrap();
+        }))
+        .finalize()
+        .unwrap();
+
+    executor.execute();
+    assert_eq!(
+        rx.try_iter().collect::<Vec<i32>>(),
+        (0..10).map(|key| key * 10).collect::<Vec<i32>>()
+    );
+}
+
+#[test]
+fn not_fused() {
+    let executor = Executor::new();
+
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(0..10), Duration::new(0, 1_000_000))
+        .unwrap()
+        .apply(Task::new_async("Lookup", (), |task, key| async move {
+            Some(lookup(key).await)
+        }))
+        .apply(Task::new("Increment", (), |task, event: i32| {
+            task.emit(event + 1)
+        }))
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, event| task.state.push(event))
+                .on_end(|task| task.exit(task.state.clone())),
+        );
+    let handle = pipeline.finalize().unwrap();
+
+    assert_eq!(
+        result.wait(),
+        (0..10).map(|key| key * 10 + 1).collect::<Vec<i32>>()
+    );
+    // Only the tasks after the asynchronous task are fused
+    assert_eq!(handle.graph().fused, vec![(2, 3)]);
+}
@@ -0,0 +1,76 @@
+#![allow(unused)]
+
+use arctime::data::*;
+use arctime::executor::*;
+use arctime::pipeline::*;
+use arctime::source::*;
+use arctime::task::*;
+
+use kompact::prelude::*;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn slow_sink() {
+    let executor = Executor::new();
+    let emitted = Arc::new(AtomicUsize::new(0));
+    let consumed = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+
+    let counter = emitted.clone();
+    let (sink_consumed, sink_peak) = (consumed.clone(), peak.clone());
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(0..100), Duration::new(0, 1_000_000))
+        .unwrap()
+        .apply(
+            Task::new("Map", (), move |task, event| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                task.emit(event + 1)
+            })
+            .set_capacity(4),
+        )
+        .sink_terminating(
+            Task::new("Slow sum", 0, move |task, event| {
+                std::thread::sleep(Duration::from_millis(10));
+                let consumed = sink_consumed.fetch_add(1, Ordering::SeqCst) + 1;
+                let in_flight = emitted.load(Ordering::SeqCst) - consumed;
+                sink_peak.fetch_max(in_flight, Ordering::SeqCst);
+                task.state += event;
+            })
+            .set_capacity(2)
+            .on_end(|task| task.exit(task.state)),
+        );
+    pipeline.finalize().unwrap();
+
+    assert_eq!(result.wait(), 5050);
+    assert_eq!(consumed.load(Ordering::SeqCst), 100);
+    // Events between the map and the sink never exceed the buffers of the two tasks, even though
+    // the source produces ten times faster than the sink consumes
+    assert!(peak.load(Ordering::SeqCst) <= 2 * (4 + 2));
+}
+
+#[test]
+fn batched() {
+    let executor = Executor::new();
+
+    // Batches are larger than the capacity of the sink, so they are split to fit its credit
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(0..200), Pacing::Unbounded)
+        .unwrap()
+        .apply(
+            Task::new("Map", (), |task, event| task.emit(event + 1))
+                .set_batching(Duration::from_millis(10)),
+        )
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, event| task.state.push(event))
+                .set_capacity(2)
+                .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    assert_eq!(result.wait(), (1..=200).collect::<Vec<_>>());
+}
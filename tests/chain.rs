@@ -0,0 +1,84 @@
+#![allow(unused)]
+
+use arctime::chain::*;
+use arctime::data::*;
+use arctime::executor::*;
+use arctime::graph::*;
+use arctime::pipeline::*;
+use arctime::source::*;
+use arctime::task::*;
+
+use kompact::prelude::*;
+use std::time::Duration;
+
+/// Double and increment the numbers 0 to 99, optionally opting the two tasks out of chaining,
+/// and return the output together with the graph of the pipeline.
+fn run(chaining: bool) -> (Vec<i32>, Graph) {
+    let executor = Executor::new();
+    let opt_out = |task: Task<(), i32, i32>| {
+        if chaining {
+            task
+        } else {
+            task.disable_chaining()
+        }
+    };
+
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(0..100), Duration::new(0, 1_000_000))
+        .unwrap()
+        .apply(opt_out(Task::new("Double", (), |task, event| {
+            task.emit(event * 2)
+        })))
+        .apply(opt_out(Task::new("Increment", (), |task, event| {
+            task.emit(event + 1)
+        })))
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, event| task.state.push(event))
+                .on_end(|task| task.exit(task.state.clone())),
+        );
+    let handle = pipeline.finalize().unwrap();
+
+    (result.wait(), handle.graph())
+}
+
+#[test]
+fn fused() {
+    let (output, graph) = run(true);
+    assert_eq!(output, (0..100).map(|x| x * 2 + 1).collect::<Vec<_>>());
+    assert_eq!(graph.fused, vec![(0, 1), (1, 2), (2, 3)]);
+    assert!(graph.to_dot().contains("1 -> 2 [style=bold];"));
+}
+
+#[test]
+fn opted_out() {
+    let (output, graph) = run(false);
+    assert_eq!(output, run(true).0);
+    assert!(graph.fused.is_empty());
+    assert!(graph.to_dot().contains("1 -> 2;"));
+    assert!(graph
+        .to_json()
+        .contains(r#"{"from":1,"to":2,"fused":false}"#));
+}
+
+#[test]
+fn fan_out() {
+    let executor = Executor::new();
+    let pipeline = executor.pipeline();
+
+    let stream = pipeline
+        .source(timestamped(0..100), Duration::new(0, 1_000_000))
+        .unwrap();
+    let (_, sum) = stream.clone().sink_terminating(
+        Task::new("Sum", 0, |task, event| task.state += event).on_end(|task| task.exit(task.state)),
+    );
+    let (pipeline, count) = stream.sink_terminating(
+        Task::new("Count", 0, |task, _| task.state += 1).on_end(|task| task.exit(task.state)),
+    );
+    let handle = pipeline.finalize().unwrap();
+
+    assert_eq!(sum.wait(), 4950);
+    assert_eq!(count.wait(), 100);
+    // A producer with more than one consumer is not fused with any of them
+    assert!(handle.graph().fused.is_empty());
+}
@@ -0,0 +1,68 @@
+#![allow(unused)]
+
+use arctime::data::*;
+use arctime::executor::*;
+use arctime::pipeline::*;
+use arctime::source::*;
+use arctime::task::*;
+
+use kompact::prelude::*;
+use std::sync::mpsc;
+use std::time::Duration;
+
+#[test]
+fn captured_environment() {
+    let executor = Executor::new();
+    let pipeline = executor.pipeline();
+
+    let threshold = 5;
+    let (tx, rx) = mpsc::channel();
+
+    pipeline
+        .source(timestamped(0..10), Duration::new(0, 5_000_000))
+        .unwrap()
+        .apply(Task::new("Filter", (), move |task, event| {
+            if event >= threshold {
+                task.emit(event);
+            }
+        }))
+        .sink(Task::new("Collect", (), move |task, event| {
+            tx.send(event).unwrap();
+        }))
+        .finalize()
+        .unwrap();
+
+    executor.execute();
+    assert_eq!(rx.try_iter().collect::<Vec<i32>>(), vec![5, 6, 7, 8, 9]);
+}
+
+#[test]
+fn captured_by_hooks() {
+    let executor = Executor::new();
+    let pipeline = executor.pipeline();
+
+    let (tx, rx) = mpsc::channel();
+    let ended = tx.clone();
+    let (watermarks, passed) = mpsc::channel();
+
+    pipeline
+        .source(timestamped(0..10), Duration::new(0, 5_000_000))
+        .unwrap()
+        .sink(
+            Task::new("Collect", (), move |task, event| {
+                tx.send(event).unwrap();
+            })
+            .on_watermark(move |task, time| watermarks.send(time).unwrap())
+            .on_end(move |task| ended.send(-1).unwrap()),
+        )
+        .finalize()
+        .unwrap();
+
+    executor.execute();
+    let events = rx.try_iter().collect::<Vec<i32>>();
+    assert_eq!(
+        events,
+        (0..10).chain(std::iter::once(-1)).collect::<Vec<_>>()
+    );
+    assert!(passed.try_iter().count() > 0);
+}
@@ -0,0 +1,150 @@
+#![allow(unused)]
+
+use arctime::control::*;
+use arctime::data::*;
+use arctime::executor::*;
+use arctime::pipeline::*;
+use arctime::port::*;
+use arctime::source::*;
+use arctime::task::*;
+
+use kompact::prelude::*;
+use std::time::Duration;
+use std::time::Instant;
+
+#[derive(Debug, Clone)]
+struct Threshold(i32);
+
+#[test]
+fn reconfigure() {
+    let executor = Executor::new();
+    let pipeline = executor.pipeline();
+
+    let filtered = pipeline
+        .source(timestamped(0..200), Duration::new(0, 5_000_000))
+        .unwrap()
+        .apply(
+            Task::new("Filter", 100, |task, event| {
+                if event > task.state {
+                    task.emit(event);
+                }
+            })
+            .on_reconfigure(|state, Threshold(threshold)| *state = threshold),
+        );
+    let reconfigurer = filtered.reconfigurer();
+
+    let (pipeline, result) = filtered.sink_terminating(
+        Task::new("Collect", Vec::new(), |task, event| {
+            if event == 150 {
+                task.send_upstream(CtrlEvent::Reconfigure(Command::new(Threshold(190))));
+            }
+            task.state.push(event);
+        })
+        .on_end(|task| task.exit(task.state.clone())),
+    );
+    // The configuration is delivered before the filter receives its first event
+    reconfigurer.reconfigure(Threshold(50));
+    pipeline.finalize().unwrap();
+
+    let events = result.wait();
+    assert!(events.starts_with(&(51..=150).collect::<Vec<_>>()));
+    assert!(events.ends_with(&(191..200).collect::<Vec<_>>()));
+    // Only events which were in flight when the sink reconfigured the filter get through
+    let in_flight = events.iter().filter(|e| (151..=190).contains(*e)).count();
+    assert!(in_flight < 5);
+}
+
+#[test]
+fn pause() {
+    let executor = Executor::new();
+    let start = Instant::now();
+
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(0..20), Duration::from_millis(1))
+        .unwrap()
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, event| {
+                if event == 5 {
+                    let id = task.ctx.id();
+                    task.send_upstream(CtrlEvent::Pause(id));
+                    task.schedule_once(Duration::from_millis(200), move |task, _| {
+                        task.send_upstream(CtrlEvent::Resume(id));
+                        Handled::Ok
+                    });
+                }
+                task.state.push(event);
+            })
+            .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    assert_eq!(result.wait(), (0..20).collect::<Vec<_>>());
+    assert!(start.elapsed() >= Duration::from_millis(200));
+}
+
+#[test]
+fn pause_shared() {
+    let executor = Executor::new();
+
+    let stream = executor
+        .pipeline()
+        .source(timestamped(0..20), Duration::from_millis(1))
+        .unwrap();
+    // The paused consumer never resumes, but the source keeps sending to the other consumer
+    let (_, paused) = stream.clone().sink_terminating(
+        Task::new("Paused", (), |task, event| {
+            let id = task.ctx.id();
+            task.send_upstream(CtrlEvent::Pause(id));
+        })
+        .on_end(|task| task.exit(())),
+    );
+    let (pipeline, result) = stream.sink_terminating(
+        Task::new("Collect", Vec::new(), |task, event| task.state.push(event))
+            .on_end(|task| task.exit(task.state.clone())),
+    );
+    pipeline.finalize().unwrap();
+
+    assert_eq!(result.wait(), (0..20).collect::<Vec<_>>());
+    paused.wait();
+}
+
+#[test]
+fn flush() {
+    let executor = Executor::new();
+    let start = Instant::now();
+
+    let stream = executor
+        .pipeline()
+        .source(timestamped(0..1000), Duration::from_millis(1))
+        .unwrap();
+    // The batch is never filled and would only be sent when the stream ends after a second
+    let batched = stream.clone().apply(
+        Task::new("Batched", (), |task, event| {
+            if event < 5 {
+                task.emit(event);
+            }
+        })
+        .set_batching(Duration::from_secs(60))
+        .hold_watermarks(),
+    );
+    let marker = stream.filter(|event| *event == 10);
+    let (pipeline, result) = batched.union(vec![marker]).sink_terminating(Task::new(
+        "Collect",
+        Vec::new(),
+        |task, event| {
+            if event == 10 {
+                let id = task.ctx.id();
+                task.send_upstream(CtrlEvent::Flush(id));
+            }
+            task.state.push(event);
+            if task.state.len() == 6 {
+                task.exit(task.state.clone());
+            }
+        },
+    ));
+    pipeline.finalize().unwrap();
+
+    assert_eq!(result.wait(), vec![10, 0, 1, 2, 3, 4]);
+    assert!(start.elapsed() < Duration::from_millis(500));
+}
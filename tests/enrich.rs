@@ -0,0 +1,160 @@
+#![allow(unused)]
+
+use arctime::data::*;
+use arctime::enrich::*;
+use arctime::executor::*;
+use arctime::pipeline::*;
+use arctime::source::*;
+use arctime::task::*;
+
+use kompact::prelude::*;
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// An in-process stand-in for a key-value service.
+#[derive(Debug, Clone)]
+struct Service(Arc<HashMap<i32, String>>);
+
+impl Service {
+    fn new() -> Self {
+        Self(Arc::new(
+            (0..10).map(|key| (key, key.to_string())).collect(),
+        ))
+    }
+
+    async fn get(self, key: i32) -> Option<String> {
+        self.0.get(&key).cloned()
+    }
+}
+
+fn run(completion: Completion) -> Vec<(i32, Option<String>)> {
+    let executor = Executor::new();
+    let pipeline = executor.pipeline();
+    let service = Service::new();
+    let (tx, rx) = mpsc::channel();
+
+    pipeline
+        .source(timestamped(0..12), Duration::new(0, 5_000_000))
+        .unwrap()
+        .enrich(4, completion, move |key| {
+            let service = service.clone();
+            async move { (key, service.get(key).await) }
+        })
+        .sink(Task::new("Collect", (), move |task, event| {
+            tx.send(event).unwrap();
+        }))
+        .finalize()
+        .unwrap();
+
+    executor.execute();
+    rx.try_iter().collect()
+}
+
+#[test]
+fn ordered() {
+    let output = run(Completion::Ordered);
+    let expected = (0..12)
+        .map(|key| {
+            (
+                key,
+                Some(key).filter(|key| *key < 10).map(|key| key.to_string()),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn unordered() {
+    let mut output = run(Completion::Unordered);
+    output.sort();
+    assert_eq!(output.len(), 12);
+    assert_eq!(output[3], (3, Some("3".to_string())));
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Event {
+    Item(i32),
+    Watermark(DateTime),
+}
+
+#[test]
+fn watermark_hold_back() {
+    let executor = Executor::new();
+    let times: HashMap<i32, DateTime> = timestamped(0..12).map(|(time, key)| (key, time)).collect();
+
+    // Earlier keys take longer to look up, so their results complete after later ones
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(0..12), Duration::new(0, 1_000_000))
+        .unwrap()
+        .enrich(4, Completion::Unordered, |key| {
+            let (promise, future) = promise();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(5 * (12 - key) as u64));
+                promise.fulfil(key).unwrap();
+            });
+            async move { future.await.unwrap() }
+        })
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, key| {
+                task.state.push(Event::Item(key))
+            })
+            .on_watermark(|task, time| task.state.push(Event::Watermark(time)))
+            .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    let events = result.wait();
+    let mut keys: Vec<i32> = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Item(key) => Some(*key),
+            Event::Watermark(_) => None,
+        })
+        .collect();
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, Event::Watermark(_))));
+    // A watermark only passes once the results of all earlier events have been emitted
+    for (position, event) in events.iter().enumerate() {
+        if let Event::Watermark(watermark) = event {
+            for (key, time) in &times {
+                if time <= watermark {
+                    assert!(events[..position].contains(&Event::Item(*key)));
+                }
+            }
+        }
+    }
+    keys.sort_unstable();
+    assert_eq!(keys, (0..12).collect::<Vec<_>>());
+}
+
+#[test]
+fn event_times() {
+    let executor = Executor::new();
+    let times: HashMap<i32, DateTime> = timestamped(0..12).map(|(time, key)| (key, time)).collect();
+
+    // Results keep the time of their input event rather than the watermark of the task
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(0..12), Duration::new(0, 1_000_000))
+        .unwrap()
+        .enrich(4, Completion::Unordered, |key| async move { key })
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, key| {
+                let time = task.event_time;
+                task.state.push((key, time))
+            })
+            .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    let results = result.wait();
+    assert_eq!(results.len(), 12);
+    for (key, time) in results {
+        assert_eq!(time, times[&key]);
+    }
+}
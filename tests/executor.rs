@@ -0,0 +1,34 @@
+#![allow(unused)]
+
+use arctime::data::*;
+use arctime::executor::*;
+use arctime::pipeline::*;
+use arctime::source::*;
+use arctime::task::*;
+
+use kompact::prelude::*;
+use std::time::Duration;
+
+#[test]
+fn terminates() {
+    let executor = Executor::new();
+
+    executor
+        .pipeline()
+        .source(timestamped(0..10), Duration::from_millis(1))
+        .unwrap()
+        .sink(Task::new("Print", (), |task, event| {
+            info!(task.ctx.log(), "{}", event);
+        }))
+        .finalize()
+        .unwrap();
+    // A pipeline which is never finalized does not keep the executor running
+    let _ = executor
+        .pipeline()
+        .source(timestamped(0..10), Duration::from_millis(1))
+        .unwrap()
+        .sink(Task::new("Unused", (), |_, _| {}));
+
+    // Returns once the finite pipeline has finished
+    executor.execute();
+}
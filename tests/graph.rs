@@ -0,0 +1,120 @@
+#![allow(unused)]
+
+use arctime::data::*;
+use arctime::executor::*;
+use arctime::graph::*;
+use arctime::pipeline::*;
+use arctime::source::*;
+use arctime::task::*;
+
+use kompact::prelude::*;
+use std::time::Duration;
+
+#[test]
+fn topology() {
+    let executor = Executor::new();
+    let pipeline = executor.pipeline();
+
+    let stream = pipeline
+        .source(timestamped(0..10), Duration::new(0, 5_000_000))
+        .unwrap()
+        .apply(Task::new("Double", (), |task, event: i32| {
+            task.emit(event * 2)
+        }));
+    let handle = stream
+        .sink(Task::new("Print", (), |task, event| {
+            info!(task.ctx.log(), "{}", event);
+        }))
+        .finalize()
+        .unwrap();
+
+    let graph = handle.graph();
+    let names = graph.nodes.iter().map(|node| node.name).collect::<Vec<_>>();
+    assert_eq!(names, vec!["Source", "Double", "Print"]);
+    assert_eq!(graph.edges, vec![(0, 1), (1, 2)]);
+    // Each task has one producer and one consumer, so all of them are fused
+    assert_eq!(graph.fused, vec![(0, 1), (1, 2)]);
+    assert!(graph.to_dot().contains("0 -> 1 [style=bold];"));
+    assert!(graph
+        .to_json()
+        .contains(r#"{"from":1,"to":2,"fused":true}"#));
+
+    executor.execute();
+}
+
+#[test]
+fn dangling_stream() {
+    let executor = Executor::new();
+    let pipeline = executor.pipeline();
+
+    let stream = pipeline
+        .source(timestamped(0..10), Duration::new(0, 5_000_000))
+        .unwrap();
+    stream
+        .clone()
+        .apply(Task::new("Unused", (), |task, event: i32| task.emit(event)));
+    let result = stream
+        .sink(Task::new("Print", (), |task, event| {
+            info!(task.ctx.log(), "{}", event);
+        }))
+        .finalize();
+
+    assert!(matches!(
+        result,
+        Err(GraphError::DanglingStream { name: "Unused", .. })
+    ));
+
+    // The tasks of a rejected pipeline do not keep the executor running
+    executor.execute();
+}
+
+#[test]
+fn dangling_route() {
+    let executor = Executor::new();
+    let pipeline = executor.pipeline();
+
+    let mut routes = pipeline
+        .source(timestamped(0..10), Duration::new(0, 5_000_000))
+        .unwrap()
+        .route(2, |event| (event % 2) as usize);
+    routes.pop();
+    let result = routes
+        .pop()
+        .unwrap()
+        .sink(Task::new("Print", (), |task, event| {
+            info!(task.ctx.log(), "{}", event);
+        }))
+        .finalize();
+
+    assert!(matches!(
+        result,
+        Err(GraphError::DanglingStream { name: "Route", .. })
+    ));
+
+    executor.execute();
+}
+
+#[test]
+fn escaped_names() {
+    let executor = Executor::new();
+    let pipeline = executor.pipeline();
+
+    let handle = pipeline
+        .source(timestamped(0..10), Duration::new(0, 5_000_000))
+        .unwrap()
+        .sink(Task::new(
+            "Print\t\"all\"\n\u{1}",
+            (),
+            |task, event: i32| {
+                info!(task.ctx.log(), "{}", event);
+            },
+        ))
+        .finalize()
+        .unwrap();
+
+    let graph = handle.graph();
+    assert!(graph.to_json().contains(r#"Print\t\"all\"\n\u0001"#));
+    assert!(graph.to_dot().contains(r#"Print\t\"all\"\n\u0001"#));
+
+    executor.execute();
+}
@@ -0,0 +1,103 @@
+#![allow(unused)]
+
+use arctime::data::*;
+use arctime::executor::*;
+use arctime::pipeline::*;
+use arctime::source::*;
+use arctime::status::*;
+use arctime::task::*;
+
+use kompact::prelude::*;
+use std::time::Duration;
+
+/// Statuses ordered by task name, since tasks are registered in the order they are started.
+fn sorted(mut statuses: Vec<(&'static str, TaskStatus)>) -> Vec<(&'static str, TaskStatus)> {
+    statuses.sort_by_key(|(name, _)| *name);
+    statuses
+}
+
+#[test]
+fn completion() {
+    let executor = Executor::new();
+
+    let handle = executor
+        .pipeline()
+        .source(timestamped(0..10), Duration::from_millis(1))
+        .unwrap()
+        .apply(Task::new("Map", (), |task, event| task.emit(event + 1)))
+        .sink(Task::new("Print", (), |task, event| {
+            info!(task.ctx.log(), "{}", event);
+        }))
+        .finalize()
+        .unwrap();
+
+    assert_eq!(
+        sorted(handle.await_completion()),
+        vec![
+            ("Map", TaskStatus::Finished),
+            ("Print", TaskStatus::Finished),
+            ("Source", TaskStatus::Finished),
+        ]
+    );
+}
+
+#[test]
+fn cancel() {
+    let executor = Executor::new();
+
+    let handle = executor
+        .pipeline()
+        .source(timestamped(0..1_000_000), Duration::from_millis(1))
+        .unwrap()
+        .sink(Task::new("Print", (), |task, event| {
+            info!(task.ctx.log(), "{}", event);
+        }))
+        .finalize()
+        .unwrap();
+
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(handle
+        .statuses()
+        .iter()
+        .all(|(_, status)| *status == TaskStatus::Running));
+    handle.cancel();
+
+    assert_eq!(
+        sorted(handle.await_completion()),
+        vec![
+            ("Print", TaskStatus::Cancelled),
+            ("Source", TaskStatus::Cancelled),
+        ]
+    );
+}
+
+#[test]
+fn failure() {
+    let executor = Executor::new();
+
+    let handle = executor
+        .pipeline()
+        .source(timestamped(0..1_000_000), Duration::from_millis(1))
+        .unwrap()
+        .apply(Task::new("Fail", (), |task, event| {
+            if event == 5 {
+                panic!("Failed at {}", event);
+            }
+            task.emit(event);
+        }))
+        .sink(Task::new("Print", (), |task, event| {
+            info!(task.ctx.log(), "{}", event);
+        }))
+        .finalize()
+        .unwrap();
+
+    // The failure of a task cancels the rest of its pipeline
+    assert_eq!(
+        sorted(handle.await_completion()),
+        vec![
+            ("Fail", TaskStatus::Failed("Failed at 5".to_string())),
+            ("Print", TaskStatus::Cancelled),
+            ("Source", TaskStatus::Cancelled),
+        ]
+    );
+}
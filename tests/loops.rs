@@ -0,0 +1,254 @@
+#![allow(unused)]
+
+use arctime::data::*;
+use arctime::executor::*;
+use arctime::loops::*;
+use arctime::pipeline::*;
+use arctime::source::*;
+use arctime::stream::*;
+use arctime::task::*;
+
+use kompact::prelude::*;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[test]
+fn iterate_terminates() {
+    let executor = Executor::new();
+
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(0..10), Duration::new(0, 1_000_000))
+        .unwrap()
+        .iterate(|stream: Stream<i32>| {
+            let feedback = stream
+                .clone()
+                .apply(Task::new("Increment", (), |task, event| {
+                    if event < 100 {
+                        task.emit(event + 1);
+                    }
+                }));
+            let output = stream.apply(Task::new("Done", (), |task, event| {
+                if event >= 100 {
+                    task.emit(event);
+                }
+            }));
+            (feedback, output)
+        })
+        .sink_terminating(
+            Task::new("Count", 0, |task, _| task.state += 1).on_end(|task| task.exit(task.state)),
+        );
+    pipeline.finalize().unwrap();
+
+    assert_eq!(result.wait(), 10);
+    executor.execute();
+}
+
+#[test]
+fn iterate_fan_out() {
+    let executor = Executor::new();
+
+    // The output of the loop head is consumed by three tasks
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(0..10), Duration::new(0, 1_000_000))
+        .unwrap()
+        .iterate(|stream: Stream<i32>| {
+            let feedback = stream
+                .clone()
+                .apply(Task::new("Increment", (), |task, event| {
+                    if event < 100 {
+                        task.emit(event + 10);
+                    }
+                }));
+            let done = stream.clone().apply(Task::new("Done", (), |task, event| {
+                if event >= 100 {
+                    task.emit(event);
+                }
+            }));
+            let odd = stream.apply(Task::new("Odd", (), |task, event| {
+                if event >= 100 && event % 2 == 1 {
+                    task.emit(-event);
+                }
+            }));
+            (feedback, done.union(vec![odd]))
+        })
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, event| task.state.push(event))
+                .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    let mut events = result.wait();
+    events.sort_unstable();
+    assert_eq!(
+        events,
+        vec![-109, -107, -105, -103, -101, 100, 101, 102, 103, 104, 105, 106, 107, 108, 109]
+    );
+    executor.execute();
+}
+
+#[test]
+fn structured_loop() {
+    let executor = Executor::new();
+
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(0..3), Duration::new(0, 1_000_000))
+        .unwrap()
+        .structured_loop(
+            |entry: Stream<i32>, feedback: Stream<i32>| {
+                let stream = entry.union(vec![feedback]).apply(Task::new(
+                    "Increment",
+                    (),
+                    |task: &mut Task<(), i32, i32, Never>, event| {
+                        // The event being processed holds back its own pointstamp
+                        let pointstamp: Vec<i32> = task.scopes.iter().map(|f| f.counter).collect();
+                        let progress = &task.scopes.last().unwrap().progress;
+                        assert!(!progress.is_complete(&pointstamp));
+                        task.emit(event + 1);
+                    },
+                ));
+                (stream.clone(), stream)
+            },
+            |_, Scope([i])| i < 5,
+        )
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, event| task.state.push(event))
+                .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    let mut events = result.wait();
+    events.sort_unstable();
+    let mut expected: Vec<i32> = (0..3).flat_map(|x| (1..=5).map(move |i| x + i)).collect();
+    expected.sort_unstable();
+    assert_eq!(events, expected);
+}
+
+lazy_static! {
+    /// Round and aggregate of each superstep, in the order they complete.
+    static ref SUPERSTEPS: Mutex<Vec<(i32, i32)>> = Mutex::new(Vec::new());
+}
+
+#[test]
+fn supersteps() {
+    let executor = Executor::new();
+
+    // Each element is followed by a watermark, and is therefore iterated in its own epoch
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(vec![3, 5]), Duration::from_millis(50))
+        .unwrap()
+        .supersteps(
+            0,
+            |messages| messages.map(|x| x - 1),
+            |last, x| *last = *x,
+            |last, round| {
+                SUPERSTEPS.lock().unwrap().push((round, *last));
+                *last == 0
+            },
+        )
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, event| task.state.push(event))
+                .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    assert_eq!(result.wait(), vec![0, 0]);
+    // The first epoch converges before the second one enters the iteration
+    assert_eq!(
+        *SUPERSTEPS.lock().unwrap(),
+        vec![
+            (0, 2),
+            (1, 1),
+            (2, 0),
+            (0, 4),
+            (1, 3),
+            (2, 2),
+            (3, 1),
+            (4, 0)
+        ]
+    );
+    executor.execute();
+}
+
+/// Undirected edges of a graph with the components {0, 1, 2}, {3, 4} and {5}.
+const EDGES: [(u32, u32); 4] = [(0, 1), (1, 2), (3, 4), (5, 5)];
+
+fn neighbours(vertex: u32) -> Vec<u32> {
+    EDGES
+        .iter()
+        .filter_map(|&(a, b)| match vertex {
+            _ if vertex == a => Some(b),
+            _ if vertex == b => Some(a),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Propagate the lowest label of each vertex to its neighbours until no label changes.
+fn propagate(
+    entry: Stream<(u32, u32)>,
+    feedback: Stream<(u32, u32)>,
+) -> (Stream<(u32, u32)>, Stream<(u32, u32)>) {
+    let labels = entry.union(vec![feedback]).apply(Task::new(
+        "Propagate",
+        HashMap::new(),
+        |task: &mut Task<HashMap<u32, u32>, (u32, u32), (u32, u32), Never>, (vertex, label)| {
+            let current = task.state.entry(vertex).or_insert(u32::MAX);
+            if label < *current {
+                *current = label;
+                task.emit((vertex, label));
+            }
+        },
+    ));
+    let messages = labels.clone().flat_map(|(vertex, label)| {
+        neighbours(vertex)
+            .into_iter()
+            .map(move |neighbour| (neighbour, label))
+            .collect::<Vec<_>>()
+    });
+    (messages, labels)
+}
+
+#[test]
+fn connected_components() {
+    let executor = Executor::new();
+
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(
+            timestamped((0..6).map(|v| (v, v)).collect::<Vec<_>>()),
+            Duration::new(0, 1_000_000),
+        )
+        .unwrap()
+        // The outer loop feeds the labels of the inner fixpoint back into it once more, which
+        // must converge without changing any label
+        .structured_loop(
+            |entry: Stream<(u32, u32)>, feedback: Stream<(u32, u32)>| {
+                let labels = entry
+                    .union(vec![feedback])
+                    .structured_loop(propagate, |_, Scope([_, _])| true);
+                (labels.clone(), labels)
+            },
+            |_, Scope([outer])| outer < 2,
+        )
+        .fold(HashMap::new(), |labels, (vertex, label)| {
+            let current = labels.entry(vertex).or_insert(label);
+            *current = label.min(*current);
+        })
+        .sink_terminating(
+            Task::new("Collect", HashMap::new(), |task, labels| {
+                task.state = labels
+            })
+            .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    let mut labels: Vec<(u32, u32)> = result.wait().into_iter().collect();
+    labels.sort_unstable();
+    assert_eq!(labels, vec![(0, 0), (1, 0), (2, 0), (3, 3), (4, 3), (5, 5)]);
+}
@@ -0,0 +1,139 @@
+#![allow(unused)]
+
+use arctime::data::*;
+use arctime::executor::*;
+use arctime::keyed::*;
+use arctime::operators::*;
+use arctime::pipeline::*;
+use arctime::source::*;
+use arctime::stream::*;
+use arctime::task::*;
+
+use kompact::prelude::*;
+use std::time::Duration;
+
+#[test]
+fn functional() {
+    let executor = Executor::new();
+
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(0..10), Duration::new(0, 1_000_000))
+        .unwrap()
+        .map(|event| event + 1)
+        .filter(|event| event % 2 == 0)
+        .flat_map(|event| vec![event, event])
+        .filter_map(|event| if event < 10 { Some(event) } else { None })
+        .inspect(|event| assert!(*event < 10))
+        .scan(0, |count, event| {
+            *count += 1;
+            (*count, event)
+        })
+        .fold(Vec::new(), |events, event| events.push(event))
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, events| task.state = events)
+                .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    assert_eq!(
+        result.wait(),
+        vec![
+            (1, 2),
+            (2, 2),
+            (3, 4),
+            (4, 4),
+            (5, 6),
+            (6, 6),
+            (7, 8),
+            (8, 8)
+        ]
+    );
+}
+
+#[test]
+fn keyed() {
+    let executor = Executor::new();
+
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(0..10), Duration::new(0, 1_000_000))
+        .unwrap()
+        .key_by(|event| event % 2)
+        .sum()
+        .fold(Vec::new(), |sums, sum| sums.push(sum))
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, sums| task.state = sums)
+                .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    let sums = result.wait();
+    assert_eq!(sums.len(), 10);
+    assert_eq!(sums[8], (0, 20));
+    assert_eq!(sums[9], (1, 25));
+}
+
+/// Aggregate the numbers 0 to 9 by parity, and collect the results.
+fn aggregate<O: DataReqs>(
+    f: impl FnOnce(KeyedStream<i32, i32>) -> Stream<(i32, O)>,
+) -> Vec<(i32, O)> {
+    let executor = Executor::new();
+
+    let stream = executor
+        .pipeline()
+        .source(timestamped(0..10), Duration::new(0, 1_000_000))
+        .unwrap()
+        .key_by(|event| event % 2);
+    let (pipeline, result) = f(stream)
+        .fold(Vec::new(), |results, result| results.push(result))
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, results| task.state = results)
+                .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    result.wait()
+}
+
+#[test]
+fn keyed_min_max() {
+    let mins = aggregate(|stream| stream.min());
+    assert!(mins.iter().all(|(key, min)| key == min));
+
+    let maxs = aggregate(|stream| stream.max());
+    assert_eq!(
+        maxs,
+        (0..10).map(|event| (event % 2, event)).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn keyed_count() {
+    let counts = aggregate(|stream| stream.count());
+    assert_eq!(
+        counts,
+        (0..10)
+            .map(|event| (event % 2, event as u64 / 2 + 1))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn keyed_avg() {
+    let avgs = aggregate(|stream| stream.avg());
+    assert_eq!(avgs.len(), 10);
+    assert_eq!(avgs[0], (0, 0.0));
+    assert_eq!(avgs[3], (1, 2.0));
+    assert_eq!(avgs[8], (0, 4.0));
+    assert_eq!(avgs[9], (1, 5.0));
+}
+
+#[test]
+fn keyed_reduce() {
+    // Events of a key are combined in the order they arrive
+    let reduced = aggregate(|stream| stream.reduce(|acc, event| acc * 10 + event));
+    assert_eq!(reduced.len(), 10);
+    assert_eq!(reduced[8], (0, 2468));
+    assert_eq!(reduced[9], (1, 13579));
+}
@@ -4,6 +4,7 @@ use arctime::client::*;
 use arctime::data::*;
 use arctime::executor::*;
 use arctime::pipeline::*;
+use arctime::source::*;
 use arctime::task::*;
 
 use kompact::prelude::*;
@@ -14,7 +15,8 @@ fn main() {
 
     executor
         .pipeline()
-        .source(0..200, Duration::new(0, 50_000_000))
+        .source(timestamped(0..200), Duration::new(0, 50_000_000))
+        .unwrap()
         .apply(Task::new("Map", (), |task, event| task.emit(event + 1)))
         .apply(Task::new("Filter", (), |task, event| {
             if event % 2 == 0 {
@@ -27,17 +29,20 @@ fn main() {
         }))
         .apply(Task::new("Nested", (), |task, event: i32| {
             task.pipeline()
-                .source(event..100, Duration::new(0, 100_000_000))
+                .source(timestamped(event..100), Duration::new(0, 100_000_000))
+                .unwrap()
                 .sink(Task::new("Inner print", (), |task, event| {
                     info!(task.ctx.log(), "Inner: {}", event);
                 }))
-                .finalize();
+                .finalize()
+                .unwrap();
             task.emit(event)
         }))
         .sink(Task::new("Print", (), |task, event| {
             info!(task.ctx.log(), "Outer: {}", event);
         }))
-        .finalize();
+        .finalize()
+        .unwrap();
 
     executor.execute();
 }
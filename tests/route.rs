@@ -0,0 +1,93 @@
+#![allow(unused)]
+
+use arctime::data::*;
+use arctime::executor::*;
+use arctime::pipeline::*;
+use arctime::source::*;
+use arctime::task::*;
+
+use kompact::prelude::*;
+use std::time::Duration;
+
+#[test]
+fn route() {
+    let executor = Executor::new();
+    let pipeline = executor.pipeline();
+
+    let mut routes = pipeline
+        .source(timestamped(0..100), Duration::new(0, 1_000_000))
+        .unwrap()
+        .route(2, |event| (event % 2) as usize);
+    let odd = routes.pop().unwrap();
+    let even = routes.pop().unwrap();
+    let (_, odd) = odd.sink_terminating(
+        Task::new("Sum", 0, |task, event| task.state += event).on_end(|task| task.exit(task.state)),
+    );
+    let (pipeline, even) = even.sink_terminating(
+        Task::new("Sum", 0, |task, event| task.state += event).on_end(|task| task.exit(task.state)),
+    );
+    pipeline.finalize().unwrap();
+
+    assert_eq!(even.wait(), 2450);
+    assert_eq!(odd.wait(), 2500);
+}
+
+#[test]
+fn route_many() {
+    let executor = Executor::new();
+    let pipeline = executor.pipeline();
+
+    let mut routes = pipeline
+        .source(timestamped(0..100), Duration::new(0, 1_000_000))
+        .unwrap()
+        .route_many(2, |event| {
+            let mut routes = Vec::new();
+            if event % 2 == 0 {
+                routes.push(0);
+            }
+            if event % 3 == 0 {
+                routes.push(1);
+            }
+            routes
+        });
+    let threes = routes.pop().unwrap();
+    let twos = routes.pop().unwrap();
+    let (_, threes) = threes.sink_terminating(
+        Task::new("Count", 0, |task, _| task.state += 1).on_end(|task| task.exit(task.state)),
+    );
+    let (pipeline, twos) = twos.sink_terminating(
+        Task::new("Count", 0, |task, _| task.state += 1).on_end(|task| task.exit(task.state)),
+    );
+    pipeline.finalize().unwrap();
+
+    assert_eq!(twos.wait(), 50);
+    assert_eq!(threes.wait(), 34);
+}
+
+#[test]
+fn union_of_routes() {
+    let executor = Executor::new();
+    let pipeline = executor.pipeline();
+
+    let mut a = pipeline
+        .source(timestamped(0..10), Duration::new(0, 1_000_000))
+        .unwrap()
+        .route(2, |event| (event % 2) as usize);
+    let mut b = pipeline
+        .source(timestamped(10..20), Duration::new(0, 1_000_000))
+        .unwrap()
+        .route(2, |event| (event % 2) as usize);
+    let (a_odd, a_even) = (a.pop().unwrap(), a.pop().unwrap());
+    let (b_odd, b_even) = (b.pop().unwrap(), b.pop().unwrap());
+    // Each consumer receives a different route of each router
+    let (_, first) = a_odd.union(vec![b_even]).sink_terminating(
+        Task::new("Sum", 0, |task, event| task.state += event).on_end(|task| task.exit(task.state)),
+    );
+    let (pipeline, second) = a_even.union(vec![b_odd]).sink_terminating(
+        Task::new("Sum", 0, |task, event| task.state += event).on_end(|task| task.exit(task.state)),
+    );
+    pipeline.finalize().unwrap();
+
+    assert_eq!(first.wait(), 25 + 70);
+    assert_eq!(second.wait(), 20 + 75);
+}
@@ -0,0 +1,77 @@
+#![allow(unused)]
+
+use arctime::data::*;
+use arctime::executor::*;
+use arctime::pipeline::*;
+use arctime::source::*;
+use arctime::status::*;
+use arctime::task::*;
+
+use kompact::prelude::*;
+use std::time::Duration;
+
+/// Re-arms itself forever, which must not keep the task from ending when it is drained.
+fn heartbeat(task: &mut Task<bool, i32, i32, Never>) {
+    task.after(Duration::from_secs(1), heartbeat);
+}
+
+#[test]
+fn drain_and_stop() {
+    let executor = Executor::new();
+
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(0..1_000_000), Duration::from_millis(1))
+        .unwrap()
+        .apply(Task::new("Delay", false, |task, event| {
+            if !task.state {
+                task.state = true;
+                heartbeat(task);
+            }
+            // Events are emitted as watermarks pass their timers, the rest when the task drains
+            task.after(Duration::from_millis(event as u64 + 1), move |task| {
+                task.emit(event)
+            });
+        }))
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, event| task.state.push(event))
+                .on_end(|task| task.exit(task.state.clone())),
+        );
+    let handle = pipeline.finalize().unwrap();
+
+    std::thread::sleep(Duration::from_millis(100));
+    handle.stop();
+
+    let events = result.wait();
+    assert!(!events.is_empty());
+    assert_eq!(events, (0..events.len() as i32).collect::<Vec<_>>());
+    let statuses = handle.await_completion();
+    assert!(statuses
+        .iter()
+        .all(|(_, status)| *status == TaskStatus::Finished));
+}
+
+#[test]
+fn rearmed_timers() {
+    let executor = Executor::new();
+
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(vec![0]), Pacing::Unbounded)
+        .unwrap()
+        .apply(Task::new("Rearm", (), |task, _| {
+            task.after(Duration::from_millis(10), |task| task.emit(1));
+            task.after(Duration::from_millis(5), |task| {
+                // Set while draining, so only the timer which expires first still fires
+                task.after(Duration::from_millis(2), |task| task.emit(2));
+                task.after(Duration::from_millis(100), |task| task.emit(3));
+            });
+        }))
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, event| task.state.push(event))
+                .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    assert_eq!(result.wait(), vec![2, 1]);
+}
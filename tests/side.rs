@@ -0,0 +1,47 @@
+#![allow(unused)]
+
+use arctime::data::*;
+use arctime::executor::*;
+use arctime::pipeline::*;
+use arctime::side::*;
+use arctime::source::*;
+use arctime::task::*;
+
+use kompact::prelude::*;
+use std::time::Duration;
+
+const ERRORS: Tag<String> = Tag::new("Errors");
+
+#[test]
+fn side_output() {
+    let executor = Executor::new();
+    let pipeline = executor.pipeline();
+
+    let parsed = pipeline
+        .source(
+            timestamped(vec!["1", "x", "3"]),
+            Duration::new(0, 1_000_000),
+        )
+        .unwrap()
+        .apply(
+            Task::new("Parse", (), |task, event: &str| {
+                match event.parse::<i32>() {
+                    Ok(number) => task.emit(number),
+                    Err(error) => task.emit_to(&ERRORS, error.to_string()),
+                }
+            })
+            .side_output(ERRORS),
+        );
+    let errors = parsed.side_output(&ERRORS);
+
+    let (_, sum) = parsed.sink_terminating(
+        Task::new("Sum", 0, |task, event| task.state += event).on_end(|task| task.exit(task.state)),
+    );
+    let (pipeline, count) = errors.sink_terminating(
+        Task::new("Count", 0, |task, _| task.state += 1).on_end(|task| task.exit(task.state)),
+    );
+    pipeline.finalize().unwrap();
+
+    assert_eq!(sum.wait(), 4);
+    assert_eq!(count.wait(), 1);
+}
@@ -0,0 +1,117 @@
+#![allow(unused)]
+
+use arctime::data::*;
+use arctime::executor::*;
+use arctime::keyed::*;
+use arctime::pipeline::*;
+use arctime::sketch::*;
+use arctime::source::*;
+use arctime::task::*;
+
+use kompact::prelude::*;
+use std::time::Duration;
+
+#[test]
+fn distinct_per_key() {
+    let executor = Executor::new();
+
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(
+            timestamped((0..100).map(|i| i % 30).collect::<Vec<_>>()),
+            Duration::new(0, 1_000_000),
+        )
+        .unwrap()
+        .key_by(|event| event % 2)
+        .sketch(HyperLogLog::new(10), |sketch| sketch.count())
+        .sink_terminating(
+            Task::new("Last", Vec::new(), |task, event| task.state.push(event))
+                .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    let counts = result.wait();
+    let (key, count) = counts[counts.len() - 1];
+    assert_eq!(key, 1);
+    // The estimate of 15 distinct values is within a few standard errors
+    assert!((13..=17).contains(&count), "{}", count);
+}
+
+#[test]
+fn window_median() {
+    let executor = Executor::new();
+
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(0..=100), Duration::new(0, 1_000_000))
+        .unwrap()
+        .window_sketch(Duration::from_secs(60), TDigest::new(100.0), |sketch| {
+            sketch.quantile(0.5)
+        })
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, event| task.state.push(event))
+                .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    let medians = result.wait();
+    assert_eq!(medians.len(), 1);
+    assert!((medians[0].unwrap() - 50.0).abs() < 1.0);
+}
+
+#[test]
+fn windows_behind_map() {
+    let time = |millis| DateTime::unix_epoch() + Duration::from_millis(millis);
+    let executor = Executor::new();
+
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(
+            vec![
+                (time(1), 1),
+                (time(2), 5),
+                (time(3), 3),
+                (time(11), 10),
+                (time(12), 30),
+                (time(13), 20),
+            ],
+            Pacing::Unbounded,
+        )
+        .unwrap()
+        .map(|event| event * 2)
+        .window_sketch(Duration::from_millis(10), TDigest::new(100.0), |sketch| {
+            sketch.quantile(1.0)
+        })
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, event| task.state.push(event))
+                .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    assert_eq!(result.wait(), vec![Some(10.0), Some(60.0)]);
+}
+
+#[test]
+fn quantile_ignores_nan() {
+    let mut digest = TDigest::new(100.0);
+    for value in vec![1.0, f64::NAN, 2.0, 3.0] {
+        digest.insert(&value);
+    }
+    let mut other = TDigest::new(100.0);
+    other.insert(&f64::NAN);
+    digest.merge(&other);
+    assert_eq!(digest.quantile(0.0), Some(1.0));
+    assert_eq!(digest.quantile(1.0), Some(3.0));
+}
+
+#[test]
+fn merge() {
+    let mut a = CountMin::new(100, 4);
+    let mut b = CountMin::new(100, 4);
+    for i in 0..10 {
+        a.insert(&(i % 2));
+        b.insert(&(i % 5));
+    }
+    a.merge(&b);
+    assert!(a.frequency(&0) >= 7);
+}
@@ -0,0 +1,95 @@
+#![allow(unused)]
+
+use arctime::data::*;
+use arctime::executor::*;
+use arctime::pipeline::*;
+use arctime::source::*;
+use arctime::task::*;
+
+use kompact::prelude::*;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Collect the elements of a paced source, and measure how long it took to emit them.
+fn run(pacing: Pacing, count: i32) -> (Vec<i32>, Duration) {
+    let executor = Executor::new();
+    let start = Instant::now();
+
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(0..count), pacing)
+        .unwrap()
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, event| task.state.push(event))
+                .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    let events = result.wait();
+    (events, start.elapsed())
+}
+
+#[test]
+fn periodic() {
+    let (events, elapsed) = run(Pacing::Periodic(Duration::from_millis(5)), 20);
+    assert_eq!(events, (0..20).collect::<Vec<_>>());
+    assert!(elapsed >= Duration::from_millis(100));
+}
+
+#[test]
+fn unbounded() {
+    let (events, elapsed) = run(Pacing::Unbounded, 10_000);
+    assert_eq!(events, (0..10_000).collect::<Vec<_>>());
+    assert!(elapsed < Duration::from_secs(10));
+}
+
+#[test]
+fn rate() {
+    let pacing = Pacing::Rate {
+        per_second: 100,
+        burst: 10,
+    };
+    let (events, elapsed) = run(pacing, 50);
+    assert_eq!(events, (0..50).collect::<Vec<_>>());
+    // Five bursts, one every 100ms
+    assert!(elapsed >= Duration::from_millis(500));
+}
+
+#[test]
+fn replay() {
+    // Elements are 1ms apart in event time, which is replayed at a tenth of the speed
+    let (events, elapsed) = run(Pacing::Replay { speed: 0.1 }, 20);
+    assert_eq!(events, (0..20).collect::<Vec<_>>());
+    assert!(elapsed >= Duration::from_millis(190));
+}
+
+#[test]
+fn invalid() {
+    let executor = Executor::new();
+    let pipeline = executor.pipeline();
+
+    let rate = Pacing::Rate {
+        per_second: 0,
+        burst: 1,
+    };
+    let burst = Pacing::Rate {
+        per_second: 1,
+        burst: 0,
+    };
+    assert!(matches!(
+        pipeline.source(timestamped(0..10), rate),
+        Err(PacingError::ZeroRate)
+    ));
+    assert!(matches!(
+        pipeline.source(timestamped(0..10), burst),
+        Err(PacingError::ZeroBurst)
+    ));
+    assert!(matches!(
+        pipeline.source(timestamped(0..10), Pacing::Replay { speed: 0.0 }),
+        Err(PacingError::InvalidSpeed(_))
+    ));
+    assert!(matches!(
+        pipeline.source(timestamped(0..10), Pacing::Replay { speed: f64::NAN }),
+        Err(PacingError::InvalidSpeed(_))
+    ));
+}
@@ -0,0 +1,27 @@
+#![allow(unused)]
+
+use arctime::data::*;
+use arctime::executor::*;
+use arctime::pipeline::*;
+use arctime::source::*;
+use arctime::task::*;
+
+use kompact::prelude::*;
+use std::time::Duration;
+
+#[test]
+fn sum() {
+    let executor = Executor::new();
+
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(0..100), Duration::new(0, 1_000_000))
+        .unwrap()
+        .sink_terminating(
+            Task::new("Sum", 0, |task, event| task.state += event)
+                .on_end(|task| task.exit(task.state)),
+        );
+    pipeline.finalize().unwrap();
+
+    assert_eq!(result.wait(), 4950);
+}
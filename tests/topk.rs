@@ -0,0 +1,152 @@
+#![allow(unused)]
+
+use arctime::data::*;
+use arctime::executor::*;
+use arctime::keyed::*;
+use arctime::pipeline::*;
+use arctime::source::*;
+use arctime::task::*;
+use arctime::topk::*;
+
+use kompact::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const EVENTS: [i32; 7] = [1, 2, 1, 3, 2, 1, 4];
+
+fn run(mode: TopK) -> Vec<Vec<(i32, u64, u64)>> {
+    let executor = Executor::new();
+
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(EVENTS.to_vec()), Duration::new(0, 1_000_000))
+        .unwrap()
+        .key_by(|event| *event)
+        .top_k(Duration::from_secs(60), 2, mode)
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, top| task.state.push(top))
+                .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    result.wait()
+}
+
+#[test]
+fn exact() {
+    assert_eq!(run(TopK::Exact), vec![vec![(1, 3, 0), (2, 2, 0)]]);
+}
+
+#[test]
+fn heavy_hitters() {
+    let mut counts = HashMap::new();
+    for event in EVENTS.iter() {
+        *counts.entry(*event).or_insert(0) += 1;
+    }
+    let windows = run(TopK::HeavyHitters { counters: 3 });
+    assert_eq!(windows.len(), 1);
+    assert_eq!(windows[0].len(), 2);
+    assert_eq!(windows[0][0], (1, 3, 0));
+    // An estimate never undercounts, and overcounts by at most its reported error
+    for (key, count, error) in &windows[0] {
+        assert!(count - error <= counts[key]);
+        assert!(counts[key] <= *count);
+    }
+}
+
+#[test]
+fn ties() {
+    let executor = Executor::new();
+
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(timestamped(vec![3, 1, 2, 3, 1, 2]), Pacing::Unbounded)
+        .unwrap()
+        .key_by(|event| *event)
+        .top_k(Duration::from_secs(60), 2, TopK::Exact)
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, top| task.state.push(top))
+                .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    // Keys with equal counts are ordered by key
+    assert_eq!(result.wait(), vec![vec![(1, 2, 0), (2, 2, 0)]]);
+
+    // The counter with the lowest count and key is replaced first
+    let mut counts = SpaceSaving::new(2);
+    for key in vec![3, 1, 2] {
+        counts.insert(key);
+    }
+    let mut counters: Vec<_> = counts.counters().map(|(k, c)| (*k, *c)).collect();
+    counters.sort_unstable();
+    assert_eq!(counters, vec![(2, (2, 1)), (3, (1, 0))]);
+}
+
+#[test]
+fn largest() {
+    let time = |millis| DateTime::unix_epoch() + Duration::from_millis(millis);
+    let executor = Executor::new();
+
+    // Events of the second window arrive before the watermark has passed the end of the first
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(
+            vec![
+                (time(1), 5),
+                (time(2), 1),
+                (time(3), 9),
+                (time(11), 4),
+                (time(12), 7),
+                (time(13), 2),
+            ],
+            Pacing::Unbounded,
+        )
+        .unwrap()
+        .largest(Duration::from_millis(10), 2)
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, largest| {
+                task.state.push(largest)
+            })
+            .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    assert_eq!(result.wait(), vec![vec![9, 5], vec![7, 4]]);
+}
+
+#[test]
+fn windows_behind_map() {
+    let time = |millis| DateTime::unix_epoch() + Duration::from_millis(millis);
+    let executor = Executor::new();
+
+    // Events keep the time of their input through the map, so they land in separate windows
+    let (pipeline, result) = executor
+        .pipeline()
+        .source(
+            vec![
+                (time(1), 1),
+                (time(2), 4),
+                (time(3), 7),
+                (time(4), 2),
+                (time(11), 5),
+                (time(12), 8),
+                (time(13), 3),
+            ],
+            Pacing::Unbounded,
+        )
+        .unwrap()
+        .map(|event| event % 3)
+        .key_by(|event| *event)
+        .top_k(Duration::from_millis(10), 2, TopK::Exact)
+        .sink_terminating(
+            Task::new("Collect", Vec::new(), |task, top| task.state.push(top))
+                .on_end(|task| task.exit(task.state.clone())),
+        );
+    pipeline.finalize().unwrap();
+
+    assert_eq!(
+        result.wait(),
+        vec![vec![(1, 3, 0), (2, 1, 0)], vec![(2, 2, 0), (0, 1, 0)]]
+    );
+}
@@ -0,0 +1,86 @@
+#![allow(unused)]
+
+use arctime::data::*;
+use arctime::executor::*;
+use arctime::pipeline::*;
+use arctime::source::*;
+use arctime::task::*;
+
+use kompact::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[test]
+fn union() {
+    let executor = Executor::new();
+    let pipeline = executor.pipeline();
+
+    let a = pipeline
+        .source(timestamped(0..10), Duration::new(0, 1_000_000))
+        .unwrap();
+    let b = pipeline
+        .source(timestamped(10..20), Duration::new(0, 2_000_000))
+        .unwrap();
+    let c = pipeline
+        .source(timestamped(20..30), Duration::new(0, 3_000_000))
+        .unwrap();
+    let (pipeline, result) = a.union(vec![b, c]).sink_terminating(
+        Task::new("Sum", 0, |task, event| task.state += event).on_end(|task| task.exit(task.state)),
+    );
+    pipeline.finalize().unwrap();
+
+    assert_eq!(result.wait(), 435);
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Event {
+    Item(i32),
+    Watermark(DateTime),
+}
+
+#[test]
+fn union_watermarks() {
+    let executor = Executor::new();
+    let pipeline = executor.pipeline();
+    let times: HashMap<i32, DateTime> = timestamped(0..10)
+        .chain(timestamped(10..20))
+        .map(|(time, event)| (event, time))
+        .collect();
+
+    // Both inputs cover the same event times, but the second one takes longer to get there
+    let a = pipeline
+        .source(timestamped(0..10), Duration::new(0, 1_000_000))
+        .unwrap();
+    let b = pipeline
+        .source(timestamped(10..20), Duration::new(0, 5_000_000))
+        .unwrap();
+    let (pipeline, result) = a.union(vec![b]).sink_terminating(
+        Task::new("Collect", Vec::new(), |task, event| {
+            task.state.push(Event::Item(event))
+        })
+        .on_watermark(|task, time| task.state.push(Event::Watermark(time)))
+        .on_end(|task| task.exit(task.state.clone())),
+    );
+    pipeline.finalize().unwrap();
+
+    let events = result.wait();
+    let watermarks: Vec<DateTime> = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Watermark(time) => Some(*time),
+            Event::Item(_) => None,
+        })
+        .collect();
+    assert!(watermarks.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(watermarks.last(), times.values().max());
+    // A watermark only passes once all events up to it have arrived from both inputs
+    for (position, event) in events.iter().enumerate() {
+        if let Event::Watermark(watermark) = event {
+            for (event, time) in &times {
+                if time <= watermark {
+                    assert!(events[..position].contains(&Event::Item(*event)));
+                }
+            }
+        }
+    }
+}